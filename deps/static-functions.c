@@ -121,4 +121,34 @@ JSExportEntry *Find_Export_Entry_real(JSContext *ctx, JSModuleDef *m, JSAtom exp
 
 JSModuleDef *JS_Find_Loaded_Module_real(JSContext *ctx, JSAtom name) {
     return js_find_loaded_module(ctx, name);
-}
\ No newline at end of file
+}
+// quickjs.h 没有导出内置对象的 class id，以下数值与 quickjs.c 中定义了 CONFIG_BIGNUM 时的枚举一致
+#define CLASS_ID_ARRAY_BUFFER 19
+#define CLASS_ID_UINT8C_ARRAY 21
+#define CLASS_ID_FLOAT64_ARRAY 31
+#define CLASS_ID_MAP 38
+#define CLASS_ID_SET 39
+
+// JS_GetOpaque 只在 class 相同时返回非空，ArrayBuffer、typed array、Map 和 Set 的 opaque
+// 总是指向其内部结构，所以不会被原型链或全局变量欺骗
+JS_BOOL JS_IsArrayBuffer_real(JSValueConst obj) {
+    return JS_GetOpaque(obj, CLASS_ID_ARRAY_BUFFER) != NULL;
+}
+
+JS_BOOL JS_IsMap_real(JSValueConst obj) {
+    return JS_GetOpaque(obj, CLASS_ID_MAP) != NULL;
+}
+
+JS_BOOL JS_IsSet_real(JSValueConst obj) {
+    return JS_GetOpaque(obj, CLASS_ID_SET) != NULL;
+}
+
+// 返回 typed array 的类型序号，从 Uint8ClampedArray 的 0 到 Float64Array 的 10，其他值返回 -1
+int JS_GetTypedArrayType_real(JSValueConst obj) {
+    JSClassID class_id;
+    for (class_id = CLASS_ID_UINT8C_ARRAY; class_id <= CLASS_ID_FLOAT64_ARRAY; class_id++) {
+        if (JS_GetOpaque(obj, class_id) != NULL)
+            return class_id - CLASS_ID_UINT8C_ARRAY;
+    }
+    return -1;
+}
@@ -0,0 +1,79 @@
+use std::fs;
+
+use anyhow::Error;
+use ez_quick_js::{
+    ffi::JS_EVAL_TYPE_GLOBAL, function::js_to_string, js_methods, Context, IntoJs, JsClass, Runtime,
+};
+
+/// 与 print_prop_cls.rs 中的 PrintClass 相同，由 derive 宏生成 JS 类的胶水代码
+#[derive(Debug, Clone, JsClass)]
+#[js(name = "Print")]
+struct PrintClass {
+    val: i32,
+}
+
+impl Drop for PrintClass {
+    fn drop(&mut self) {
+        println!("PrintClass is drop");
+    }
+}
+
+#[js_methods]
+impl PrintClass {
+    pub fn new(val: i32) -> Self {
+        println!("PrintClass constructor is called");
+        Self { val }
+    }
+
+    #[js(setter)]
+    pub fn set_val(&mut self, val: i32) {
+        println!("Print val setter is called");
+        self.val = val;
+    }
+
+    #[js(getter)]
+    pub fn val(&self) -> i32 {
+        println!("Print val getter is called");
+        self.val
+    }
+
+    #[js(name = "PrintTestFunc")]
+    pub fn test_func(&self) {
+        println!("Print Value ~: {}", self.val);
+    }
+}
+
+fn main() -> Result<(), Error> {
+    // load js script
+    let file_name = "./examples/print_prop_cls.js";
+    let code = &fs::read_to_string(file_name)?;
+
+    let rt = Runtime::new(None);
+    let ctx = &rt.create_context();
+
+    ctx.register_class::<PrintClass>()?;
+    add_global_print(ctx)?;
+
+    println!("Eval script:");
+    let _rst = ctx.eval(code, file_name, (JS_EVAL_TYPE_GLOBAL) as i32)?;
+
+    Ok(())
+}
+
+fn add_global_print(ctx: &Context) -> Result<(), Error> {
+    let log = ctx.new_function("log", |ctx, _this, args| {
+        let items = args
+            .iter()
+            .map(|item| js_to_string(ctx, item))
+            .collect::<Result<Vec<_>, _>>()?;
+        println!("{}", items.join(" "));
+
+        ().into_js(ctx)
+    })?;
+
+    let console = ctx.new_object()?;
+    console.set_property("log", log)?;
+    ctx.get_global_object().set_property("console", console)?;
+
+    Ok(())
+}
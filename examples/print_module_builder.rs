@@ -0,0 +1,40 @@
+use std::fs;
+
+use anyhow::Error;
+use ez_quick_js::{
+    ffi::{JS_EVAL_TYPE_GLOBAL, JS_EVAL_TYPE_MODULE},
+    function::js_to_string,
+    IntoJs, Runtime,
+};
+
+/// 与 print_module.rs 相同，由 ModuleBuilder 创建模块，无需静态函数表
+fn main() -> Result<(), Error> {
+    // load js script
+    let file_name = "./examples/print_module.js";
+    let code = &fs::read_to_string(file_name)?;
+
+    let rt = Runtime::new(None);
+    let ctx = &rt.create_context();
+
+    // 创建模块 m 并导出 Print 函数
+    ctx.module("m")
+        .function("Print", |ctx, _this, args| {
+            let items = args
+                .iter()
+                .map(|item| js_to_string(ctx, item))
+                .collect::<Result<Vec<_>, _>>()?;
+            println!("{}", items.join(" "));
+
+            ().into_js(ctx)
+        })
+        .build()?;
+
+    println!("Eval script:");
+    ctx.eval(
+        code,
+        file_name,
+        (JS_EVAL_TYPE_GLOBAL | JS_EVAL_TYPE_MODULE) as i32,
+    )?;
+
+    Ok(())
+}
@@ -10,14 +10,14 @@ use ez_quick_js::ffi::{
     JS_GetOpaque, JS_GetOpaque2, JS_GetPropertyStr, JS_GetRuntime,
     JS_NewCFunction2, JS_NewClass, JS_NewObject, JS_NewObjectProtoClass,
     JS_SetClassProto, JS_SetConstructor, JS_SetModuleExport, JS_SetOpaque,
-    JS_SetPropertyFunctionList, JS_ToInt32, JS_EVAL_TYPE_GLOBAL, JS_EVAL_TYPE_MODULE, JS_TAG_INT,
+    JS_SetPropertyFunctionList, JS_EVAL_TYPE_GLOBAL, JS_EVAL_TYPE_MODULE,
 };
 use ez_quick_js::function::{add_module_export, new_class_id, C_FUNC_DEF, C_GET_SET_DEF};
 use ez_quick_js::{
     ffi::{JSCFunctionListEntry, JSContext, JSValue},
     Context, Runtime,
 };
-use ez_quick_js::{JsModuleDef, JS_EXCEPTION, JS_UNDEFINED};
+use ez_quick_js::{Args, JsModuleDef, JS_EXCEPTION, JS_UNDEFINED};
 use once_cell::sync::Lazy;
 
 #[derive(Debug, Clone)]
@@ -86,17 +86,13 @@ unsafe extern "C" fn js_print_constructor(
     println!("PrintClass constructor is called");
 
     // 提取参数并转换为 native 类型
-    let args = std::slice::from_raw_parts(argv, argc as usize);
-    let param = if argc > 0 {
-        let mut tmp = 0;
-        let rst = JS_ToInt32(ctx, &mut tmp as _, args[0]);
-        if rst != 0 {
-            return JS_EXCEPTION;
-        }
-
-        tmp
-    } else {
-        return JS_EXCEPTION;
+    let param = Context::with_raw(ctx, |ctx| {
+        let args = Args::from_raw(ctx, argc, argv);
+        args.get::<i32>(0).map_err(|err| ctx.throw_error(err))
+    });
+    let param = match param {
+        Ok(val) => val,
+        Err(exception) => return exception,
     };
 
     // 生成 native 对象
@@ -183,21 +179,21 @@ fn native_print_val_getter(print: &PrintClass) -> i32 {
 unsafe extern "C" fn js_print_val_setter(
     ctx: *mut JSContext,
     this_val: JSValue,
-    val: JSValue,
+    mut val: JSValue,
 ) -> JSValue {
     let native_print: *mut PrintClass = JS_GetOpaque2(ctx, this_val, *PRINT_CLASS_ID) as _;
     if native_print == null_mut() {
         return JS_EXCEPTION;
     }
 
-    let mut param = 0;
-    if val.tag != JS_TAG_INT.into() {
-        return JS_EXCEPTION;
-    } else {
-        if JS_ToInt32(ctx, &mut param, val) != 0 {
-            return JS_EXCEPTION;
-        }
-    }
+    let param = Context::with_raw(ctx, |ctx| {
+        let args = Args::from_raw(ctx, 1, &mut val);
+        args.get::<i32>(0).map_err(|err| ctx.throw_error(err))
+    });
+    let param = match param {
+        Ok(val) => val,
+        Err(exception) => return exception,
+    };
 
     // 调用 native 方法
     native_print_val_setter(native_print.as_mut().unwrap(), param);
@@ -7,7 +7,7 @@ use anyhow::Error;
 use ez_quick_js::ffi::{
     JS_FreeValue, JS_IsException, JS_NewInt32, JS_ToStr, JSClassDef, JSClassID, JSRuntime,
     JS_GetOpaque, JS_GetOpaque2, JS_GetPropertyStr, JS_NewObjectProtoClass, JS_SetOpaque,
-    JS_ToInt32, JS_EVAL_TYPE_GLOBAL, JS_TAG_INT,
+    JS_EVAL_TYPE_GLOBAL,
 };
 use ez_quick_js::function::{
     new_c_function2, new_class, new_class_id, set_class_proto, set_constructor,
@@ -17,7 +17,7 @@ use ez_quick_js::{
     ffi::{JSCFunctionListEntry, JSContext, JSValue},
     Context, Runtime,
 };
-use ez_quick_js::{JsValue, JS_EXCEPTION, JS_NULL, JS_UNDEFINED};
+use ez_quick_js::{Args, JsValue, JS_EXCEPTION, JS_NULL, JS_UNDEFINED};
 use once_cell::sync::Lazy;
 
 #[derive(Debug, Clone)]
@@ -89,17 +89,13 @@ unsafe extern "C" fn js_printclass_constructor(
     println!("PrintClass constructor is called");
 
     // 提取参数并转换为 native 类型
-    let args = std::slice::from_raw_parts(argv, argc as usize);
-    let param = if argc > 0 {
-        let mut tmp = 0;
-        let rst = JS_ToInt32(ctx, &mut tmp as _, args[0]);
-        if rst != 0 {
-            return JS_EXCEPTION;
-        }
-
-        tmp
-    } else {
-        return JS_EXCEPTION;
+    let param = Context::with_raw(ctx, |ctx| {
+        let args = Args::from_raw(ctx, argc, argv);
+        args.get::<i32>(0).map_err(|err| ctx.throw_error(err))
+    });
+    let param = match param {
+        Ok(val) => val,
+        Err(exception) => return exception,
     };
 
     // 生成 native 对象
@@ -186,21 +182,21 @@ fn native_print_val_getter(print: &PrintClass) -> i32 {
 unsafe extern "C" fn js_print_val_setter(
     ctx: *mut JSContext,
     this_val: JSValue,
-    val: JSValue,
+    mut val: JSValue,
 ) -> JSValue {
     let native_print: *mut PrintClass = JS_GetOpaque2(ctx, this_val, *PRINT_CLASS_ID) as _;
     if native_print == null_mut() {
         return JS_EXCEPTION;
     }
 
-    let mut param = 0;
-    if val.tag != JS_TAG_INT.into() {
-        return JS_EXCEPTION;
-    } else {
-        if JS_ToInt32(ctx, &mut param, val) != 0 {
-            return JS_EXCEPTION;
-        }
-    }
+    let param = Context::with_raw(ctx, |ctx| {
+        let args = Args::from_raw(ctx, 1, &mut val);
+        args.get::<i32>(0).map_err(|err| ctx.throw_error(err))
+    });
+    let param = match param {
+        Ok(val) => val,
+        Err(exception) => return exception,
+    };
 
     // 调用 native 方法
     native_print_val_setter(native_print.as_mut().unwrap(), param);
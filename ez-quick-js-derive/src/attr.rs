@@ -0,0 +1,56 @@
+use syn::{Attribute, LitStr};
+
+/// Options of the `#[js(...)]` helper attributes.
+#[derive(Default)]
+pub struct JsAttr {
+    pub name: Option<String>,
+    pub get: bool,
+    pub set: bool,
+    pub constructor: bool,
+    pub getter: bool,
+    pub setter: bool,
+    pub skip: bool,
+}
+
+impl JsAttr {
+    /// Parse the `#[js(...)]` attributes, only the options in `allowed` are accepted.
+    pub fn parse(attrs: &[Attribute], allowed: &[&str]) -> syn::Result<Self> {
+        let mut rst = JsAttr::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("js")) {
+            attr.parse_nested_meta(|meta| {
+                let key = meta
+                    .path
+                    .get_ident()
+                    .map(|ident| ident.to_string())
+                    .unwrap_or_default();
+                if !allowed.contains(&key.as_str()) {
+                    return Err(meta.error(format!(
+                        "unsupported js option, expected one of: {}",
+                        allowed.join(", ")
+                    )));
+                }
+
+                match key.as_str() {
+                    "name" => rst.name = Some(meta.value()?.parse::<LitStr>()?.value()),
+                    "get" => rst.get = true,
+                    "set" => rst.set = true,
+                    "constructor" => rst.constructor = true,
+                    "getter" => rst.getter = true,
+                    "setter" => rst.setter = true,
+                    "skip" => rst.skip = true,
+                    _ => unreachable!(),
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(rst)
+    }
+
+    /// Remove the `#[js(...)]` attributes, they are not known by the compiler outside a derive.
+    pub fn strip(attrs: &mut Vec<Attribute>) {
+        attrs.retain(|attr| !attr.path().is_ident("js"));
+    }
+}
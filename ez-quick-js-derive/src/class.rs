@@ -0,0 +1,92 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, Member};
+
+use crate::attr::JsAttr;
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let attr = JsAttr::parse(&input.attrs, &["name"])?;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "JsClass can not be derived for generic types",
+        ));
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "JsClass can only be derived for structs",
+        ));
+    };
+
+    let ident = &input.ident;
+    let class_name = attr.name.unwrap_or_else(|| ident.to_string());
+
+    let mut accessors = Vec::new();
+    let fields = match &data.fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+    for (idx, field) in fields.into_iter().enumerate() {
+        let attr = JsAttr::parse(&field.attrs, &["get", "set", "name"])?;
+        if !attr.get && !attr.set {
+            continue;
+        }
+
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(idx.into()),
+        };
+        let js_name = match (&attr.name, &field.ident) {
+            (Some(name), _) => name.clone(),
+            (None, Some(ident)) => ident.to_string(),
+            (None, None) => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "tuple struct fields need #[js(name = \"...\")]",
+                ))
+            }
+        };
+
+        let ty = &field.ty;
+        let unit = quote!(());
+        let (get_ty, getter) = if attr.get {
+            (
+                ty.to_token_stream(),
+                quote!(Some(|this: &Self| ::std::clone::Clone::clone(&this.#member))),
+            )
+        } else {
+            (unit.clone(), quote!(None))
+        };
+        let (set_ty, setter) = if attr.set {
+            (
+                ty.to_token_stream(),
+                quote!(Some(|this: &mut Self, val: #ty| this.#member = val)),
+            )
+        } else {
+            (unit, quote!(None))
+        };
+
+        accessors.push(quote! {
+            ::ez_quick_js::define_native_accessor::<Self, #get_ty, #set_ty>(
+                ctx, proto, #js_name, #getter, #setter,
+            )?;
+        });
+    }
+
+    Ok(quote! {
+        impl ::ez_quick_js::NativeClass for #ident {
+            const NAME: &'static str = #class_name;
+
+            fn define_fields(
+                ctx: &::ez_quick_js::Context,
+                proto: &::ez_quick_js::JsValue,
+            ) -> ::std::result::Result<(), ::ez_quick_js::common::Error> {
+                #(#accessors)*
+                Ok(())
+            }
+        }
+    })
+}
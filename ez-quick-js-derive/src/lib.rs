@@ -0,0 +1,51 @@
+//! Derive macros exposing Rust types as JavaScript classes of `ez-quick-js`.
+//!
+//! `#[derive(JsClass)]` implements `NativeClass` for a struct and `#[js_methods]` implements
+//! `NativeMethods` from an ordinary `impl` block, the class is then registered with
+//! `Context::register_class::<T>()`.
+
+mod attr;
+mod class;
+mod methods;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, ItemImpl};
+
+/// Implement `NativeClass` for a struct.
+///
+/// - `#[js(name = "Name")]` on the struct renames the JS class.
+/// - `#[js(get)]`, `#[js(set)]` and `#[js(name = "...")]` on a field define an accessor on the
+///   prototype, the field type must implement `Clone + IntoJs` for `get` and `FromJs` for `set`.
+#[proc_macro_derive(JsClass, attributes(js))]
+pub fn derive_js_class(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    class::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `NativeMethods` from an `impl` block of a `#[derive(JsClass)]` type.
+///
+/// - `#[js(constructor)]`, or a function named `new` without receiver, is the JS constructor.
+/// - Methods with a `&self` / `&mut self` receiver are defined on the prototype, the other
+///   functions on the constructor.
+/// - `#[js(getter)]` / `#[js(setter)]` define an accessor, a `set_` prefix is stripped from
+///   setter names.
+/// - `#[js(name = "...")]` renames a function and `#[js(skip)]` keeps it Rust only.
+///
+/// Arguments are converted with `FromJs`, results with `IntoJs`, a returned `Result` is
+/// propagated as a JS exception.
+#[proc_macro_attribute]
+pub fn js_methods(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return syn::Error::new_spanned(attr, "#[js_methods] takes no arguments")
+            .into_compile_error()
+            .into();
+    }
+
+    let item = parse_macro_input!(item as ItemImpl);
+    methods::expand(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
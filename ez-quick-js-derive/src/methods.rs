@@ -0,0 +1,305 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{FnArg, ImplItem, ImplItemFn, ItemImpl, ReturnType, Type};
+
+use crate::attr::JsAttr;
+
+enum Receiver {
+    None,
+    Ref,
+    Mut,
+}
+
+/// A function of the `impl` block exposed to scripts.
+struct JsFn {
+    ident: Ident,
+    receiver: Receiver,
+    arg_types: Vec<Type>,
+    returns_result: bool,
+}
+
+impl JsFn {
+    fn new(func: &ImplItemFn) -> syn::Result<Self> {
+        let sig = &func.sig;
+        if !sig.generics.params.is_empty() || sig.asyncness.is_some() {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "generic and async functions can not be exposed to JS, use #[js(skip)]",
+            ));
+        }
+
+        let mut receiver = Receiver::None;
+        let mut arg_types = Vec::new();
+        for input in &sig.inputs {
+            match input {
+                FnArg::Receiver(recv) if recv.reference.is_some() => {
+                    receiver = if recv.mutability.is_some() {
+                        Receiver::Mut
+                    } else {
+                        Receiver::Ref
+                    };
+                }
+                FnArg::Receiver(recv) => {
+                    return Err(syn::Error::new_spanned(
+                        recv,
+                        "only &self and &mut self receivers are supported",
+                    ))
+                }
+                FnArg::Typed(arg) => {
+                    if let Type::Reference(_) = &*arg.ty {
+                        return Err(syn::Error::new_spanned(
+                            &arg.ty,
+                            "arguments must be owned types implementing FromJs",
+                        ));
+                    }
+                    arg_types.push((*arg.ty).clone());
+                }
+            }
+        }
+
+        Ok(Self {
+            ident: sig.ident.clone(),
+            receiver,
+            arg_types,
+            returns_result: returns_result(&sig.output),
+        })
+    }
+
+    /// The closure passed to `new_function`, calling the Rust function.
+    fn closure(&self) -> TokenStream {
+        let ident = &self.ident;
+        let args = (0..self.arg_types.len())
+            .map(|idx| format_ident!("arg{}", idx))
+            .collect::<Vec<_>>();
+        let arg_types = &self.arg_types;
+        let indexes = 0..self.arg_types.len();
+        let question = self.returns_result.then(|| quote!(?));
+
+        let call = match self.receiver {
+            Receiver::None => quote!(Self::#ident(#(#args),*)),
+            Receiver::Ref => quote! {
+                ::ez_quick_js::with_native::<Self, _>(&this, |native| {
+                    Self::#ident(native, #(#args),*)
+                })?
+            },
+            Receiver::Mut => quote! {
+                ::ez_quick_js::with_native_mut::<Self, _>(&this, |native| {
+                    Self::#ident(native, #(#args),*)
+                })?
+            },
+        };
+
+        quote! {
+            |ctx, this, args| {
+                let _ = (&this, &args);
+                #(let #args: #arg_types = args.get(#indexes)?;)*
+                let rst = #call #question;
+                ::ez_quick_js::IntoJs::into_js(rst, ctx)
+            }
+        }
+    }
+}
+
+/// `true` if the function returns a `Result`, which is propagated with `?`.
+fn returns_result(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+struct Accessor {
+    name: String,
+    getter: Option<TokenStream>,
+    setter: Option<TokenStream>,
+}
+
+pub fn expand(mut item: ItemImpl) -> syn::Result<TokenStream> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "#[js_methods] must be used on an inherent impl block",
+        ));
+    }
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[js_methods] does not support generic impl blocks",
+        ));
+    }
+
+    let explicit_ctor = item.items.iter().any(|it| match it {
+        ImplItem::Fn(func) => JsAttr::parse(&func.attrs, ALLOWED)
+            .map(|attr| attr.constructor)
+            .unwrap_or(false),
+        _ => false,
+    });
+
+    let mut ctor = None;
+    let mut methods = Vec::new();
+    let mut statics = Vec::new();
+    let mut accessors: Vec<Accessor> = Vec::new();
+
+    for it in item.items.iter_mut() {
+        let ImplItem::Fn(func) = it else {
+            continue;
+        };
+        let attr = JsAttr::parse(&func.attrs, ALLOWED)?;
+        JsAttr::strip(&mut func.attrs);
+        if attr.skip {
+            continue;
+        }
+
+        let js_fn = JsFn::new(func)?;
+        let rust_name = js_fn.ident.to_string();
+        let is_ctor = attr.constructor
+            || (!explicit_ctor && rust_name == "new" && matches!(js_fn.receiver, Receiver::None));
+
+        if is_ctor {
+            if ctor.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &func.sig,
+                    "only one constructor is allowed",
+                ));
+            }
+            ctor = Some(js_fn);
+        } else if attr.getter || attr.setter {
+            if matches!(js_fn.receiver, Receiver::None) {
+                return Err(syn::Error::new_spanned(
+                    &func.sig,
+                    "getters and setters need a &self or &mut self receiver",
+                ));
+            }
+
+            let name = attr.name.clone().unwrap_or_else(|| {
+                if attr.setter {
+                    rust_name
+                        .strip_prefix("set_")
+                        .unwrap_or(&rust_name)
+                        .to_owned()
+                } else {
+                    rust_name.clone()
+                }
+            });
+            let func_value = {
+                let closure = js_fn.closure();
+                quote!(Some(ctx.new_function(#name, #closure)?))
+            };
+
+            let idx = match accessors.iter().position(|acc| acc.name == name) {
+                Some(idx) => idx,
+                None => {
+                    accessors.push(Accessor {
+                        name,
+                        getter: None,
+                        setter: None,
+                    });
+                    accessors.len() - 1
+                }
+            };
+            let slot = if attr.getter {
+                &mut accessors[idx].getter
+            } else {
+                &mut accessors[idx].setter
+            };
+            if slot.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &func.sig,
+                    "duplicated getter or setter",
+                ));
+            }
+            *slot = Some(func_value);
+        } else {
+            let name = attr.name.clone().unwrap_or(rust_name);
+            let closure = js_fn.closure();
+            let define = quote! {
+                ::ez_quick_js::define_method(ctx, obj, #name, #closure)?;
+            };
+            match js_fn.receiver {
+                Receiver::None => statics.push(define),
+                _ => methods.push(define),
+            }
+        }
+    }
+
+    let accessors = accessors.into_iter().map(|acc| {
+        let name = acc.name;
+        let getter = acc.getter.unwrap_or_else(|| quote!(None));
+        let setter = acc.setter.unwrap_or_else(|| quote!(None));
+        quote! {
+            {
+                let getter = #getter;
+                let setter = #setter;
+                let name = ctx.new_atom(#name)?;
+                ::ez_quick_js::function::define_property_getset(
+                    ctx,
+                    obj,
+                    name,
+                    getter,
+                    setter,
+                    ::ez_quick_js::ffi::JS_PROP_CONFIGURABLE as i32,
+                )?;
+            }
+        }
+    });
+
+    let ctor = ctor.map(|ctor| {
+        let ident = &ctor.ident;
+        let len = ctor.arg_types.len() as i32;
+        let args = (0..ctor.arg_types.len())
+            .map(|idx| format_ident!("arg{}", idx))
+            .collect::<Vec<_>>();
+        let arg_types = &ctor.arg_types;
+        let indexes = 0..ctor.arg_types.len();
+        let question = ctor.returns_result.then(|| quote!(?));
+
+        quote! {
+            const CONSTRUCTOR_LENGTH: i32 = #len;
+
+            fn constructor(
+                _ctx: &::ez_quick_js::Context,
+                args: ::ez_quick_js::Args,
+            ) -> ::std::result::Result<Self, ::ez_quick_js::common::Error> {
+                let _ = &args;
+                #(let #args: #arg_types = args.get(#indexes)?;)*
+                Ok(Self::#ident(#(#args),*) #question)
+            }
+        }
+    });
+
+    let self_ty = &item.self_ty;
+
+    Ok(quote! {
+        #item
+
+        impl ::ez_quick_js::NativeMethods for #self_ty {
+            #ctor
+
+            fn define_methods(
+                ctx: &::ez_quick_js::Context,
+                obj: &::ez_quick_js::JsValue,
+            ) -> ::std::result::Result<(), ::ez_quick_js::common::Error> {
+                #(#methods)*
+                #(#accessors)*
+                Ok(())
+            }
+
+            fn define_static(
+                ctx: &::ez_quick_js::Context,
+                obj: &::ez_quick_js::JsValue,
+            ) -> ::std::result::Result<(), ::ez_quick_js::common::Error> {
+                #(#statics)*
+                Ok(())
+            }
+        }
+    })
+}
+
+const ALLOWED: &[&str] = &["name", "constructor", "getter", "setter", "skip"];
@@ -0,0 +1,151 @@
+use std::{ffi::c_int, ops::Deref};
+
+use crate::{common::Error, ffi::JSValue, Context, FromJs, JsValue, JS_UNDEFINED};
+
+/// Arguments passed to a native callback.
+///
+/// Values can be read directly through `Deref<Target = [JsValue]>`, or converted with
+/// [`FromJs`] by [`Args::get`], whose errors are reported to scripts as a `TypeError`
+/// naming the argument index.
+pub struct Args<'a> {
+    ctx: &'a Context<'a>,
+    values: Vec<JsValue<'a>>,
+}
+
+impl<'a> Args<'a> {
+    pub fn new(ctx: &'a Context, values: Vec<JsValue<'a>>) -> Self {
+        Self { ctx, values }
+    }
+
+    /// Wrap the `argc`/`argv` pair received by a `JSCFunction`, the values are duplicated.
+    ///
+    /// # Safety
+    /// `argv` must point to `argc` valid values of `ctx`.
+    pub unsafe fn from_raw(ctx: &'a Context, argc: c_int, argv: *mut JSValue) -> Self {
+        let values = if argc > 0 && !argv.is_null() {
+            std::slice::from_raw_parts(argv, argc as usize)
+        } else {
+            &[]
+        };
+        let values = values
+            .iter()
+            .map(|val| {
+                let val = JsValue::new(ctx, *val);
+                val.increment_ref_count();
+                val
+            })
+            .collect();
+
+        Self { ctx, values }
+    }
+
+    pub fn context(&self) -> &'a Context<'a> {
+        self.ctx
+    }
+
+    /// Convert the argument at `idx`, a missing argument is converted from `undefined`,
+    /// so `get::<Option<T>>` reads an optional argument.
+    pub fn get<T: FromJs<'a>>(&self, idx: usize) -> Result<T, Error> {
+        let val = match self.values.get(idx) {
+            Some(val) => val.clone(),
+            None => JsValue::new(self.ctx, JS_UNDEFINED),
+        };
+
+        T::from_js(self.ctx, val).map_err(|err| match err {
+            _ if idx >= self.values.len() => Error::BadType(format!("Argument {idx} is required")),
+            Error::BadType(msg) | Error::ValueError(msg) => {
+                Error::BadType(format!("Argument {idx}: {msg}"))
+            }
+            err => err,
+        })
+    }
+
+    /// Convert the argument at `idx`, `None` if it is missing, `null` or `undefined`.
+    pub fn get_opt<T: FromJs<'a>>(&self, idx: usize) -> Result<Option<T>, Error> {
+        self.get::<Option<T>>(idx)
+    }
+
+    /// Convert all arguments from `start` on.
+    pub fn rest<T: FromJs<'a>>(&self, start: usize) -> Result<Vec<T>, Error> {
+        (start..self.values.len())
+            .map(|idx| self.get(idx))
+            .collect()
+    }
+
+    pub fn into_vec(self) -> Vec<JsValue<'a>> {
+        self.values
+    }
+}
+
+impl<'a> Deref for Args<'a> {
+    type Target = [JsValue<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl<'a> IntoIterator for Args<'a> {
+    type Item = JsValue<'a>;
+    type IntoIter = std::vec::IntoIter<JsValue<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+impl<'s, 'a> IntoIterator for &'s Args<'a> {
+    type Item = &'s JsValue<'a>;
+    type IntoIter = std::slice::Iter<'s, JsValue<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::Error, function::js_to_string, Context, IntoJs, Runtime};
+
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let join = ctx
+            .new_function("join", |ctx, _this, args| {
+                let sep: String = args.get(0)?;
+                let limit = args.get_opt::<usize>(1)?.unwrap_or(usize::MAX);
+                let items: Vec<i32> = args.rest(2)?;
+                let items = items
+                    .iter()
+                    .take(limit)
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>();
+                items.join(&sep).into_js(ctx)
+            })
+            .unwrap();
+        ctx.get_global_object().set_property("join", join).unwrap();
+
+        let script = r#"
+            function check(f) {
+                try { return f(); } catch (e) { return `${e.name}: ${e.message}`; }
+            }
+            [check(() => join("-", null, 1, 2, 3)), check(() => join("-", 2, 1, 2, 3)),
+             check(() => join()), check(() => join("-", 1, 1, "x"))].join("|")
+        "#;
+        let rst = ctx.eval(script, "<test>", flags).unwrap();
+        assert_eq!(
+            "1-2-3|1-2|TypeError: Argument 0 is required|TypeError: Argument 3: Need i32 but get String",
+            js_to_string(ctx, &rst).unwrap()
+        );
+
+        let args = Args::new(ctx, vec![ctx.get_int(1)]);
+        assert_eq!(1, args.len());
+        assert!(matches!(args.get::<bool>(0), Err(Error::BadType(_))));
+        assert_eq!(None, args.get_opt::<i32>(1).unwrap());
+    }
+}
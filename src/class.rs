@@ -0,0 +1,410 @@
+use std::{
+    any::{type_name, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+    ffi::c_int,
+    marker::PhantomData,
+    sync::{Mutex, PoisonError},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    common::{make_cstring, Error},
+    ffi::{
+        JSClassDef, JSClassID, JSContext, JSRuntime, JSValue, JS_GetOpaque, JS_GetPropertyStr,
+        JS_NewClass, JS_NewObjectClass, JS_PROP_CONFIGURABLE, JS_PROP_WRITABLE,
+    },
+    function::{
+        assert_exception, catch_callback, define_property_getset, new_c_function2, new_class_id,
+        new_object_proto_class, set_class_proto, set_constructor,
+    },
+    Args, Context, FromJs, IntoJs, JsValue, Runtime,
+};
+
+/// Class ids of the Rust types, `JS_NewClassID` is process wide so a type keeps the same id
+/// in every runtime.
+static CLASS_IDS: Lazy<Mutex<HashMap<TypeId, JSClassID>>> = Lazy::new(Default::default);
+
+fn type_class_id<T: 'static>() -> JSClassID {
+    let mut ids = CLASS_IDS.lock().unwrap_or_else(PoisonError::into_inner);
+    *ids.entry(TypeId::of::<T>()).or_insert_with(|| {
+        let mut tmp = 0;
+        new_class_id(&mut tmp)
+    })
+}
+
+unsafe extern "C" fn opaque_finalizer<T: 'static>(_rt: *mut JSRuntime, val: JSValue) {
+    let opaque = JS_GetOpaque(val, type_class_id::<T>()) as *mut RefCell<T>;
+    if !opaque.is_null() {
+        drop(Box::from_raw(opaque));
+    }
+}
+
+/// The classes registered in a runtime, keyed by the Rust type of their opaque.
+#[derive(Default)]
+pub struct ClassRegistry {
+    classes: RefCell<HashMap<TypeId, JSClassID>>,
+}
+
+impl ClassRegistry {
+    /// The class of `T`, if it is registered.
+    pub fn get<T: 'static>(&self) -> Option<JsClass<T>> {
+        self.classes
+            .borrow()
+            .get(&TypeId::of::<T>())
+            .map(|&id| JsClass::new(id))
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.classes.borrow().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Register the class of `T` named `name`, registering a type twice returns the same class.
+    pub(crate) fn register<T: 'static>(
+        &self,
+        rt: *mut JSRuntime,
+        name: &str,
+    ) -> Result<JsClass<T>, Error> {
+        if let Some(class) = self.get::<T>() {
+            return Ok(class);
+        }
+
+        let class_id = type_class_id::<T>();
+        let class_name = make_cstring(name)?;
+        let class_def = JSClassDef {
+            class_name: class_name.as_ptr(),
+            finalizer: Some(opaque_finalizer::<T>),
+            gc_mark: None,
+            call: None,
+            exotic: std::ptr::null_mut(),
+        };
+        if unsafe { JS_NewClass(rt, class_id, &class_def) } != 0 {
+            Err(Error::GeneralError(format!(
+                "Could not register class {name}"
+            )))?
+        }
+        self.classes
+            .borrow_mut()
+            .insert(TypeId::of::<T>(), class_id);
+
+        Ok(JsClass::new(class_id))
+    }
+}
+
+/// A registered JS class whose instances own a `T`, created by [`Runtime::register_class`].
+pub struct JsClass<T> {
+    id: JSClassID,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for JsClass<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for JsClass<T> {}
+
+impl<T: 'static> JsClass<T> {
+    fn new(id: JSClassID) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> JSClassID {
+        self.id
+    }
+
+    /// Create an instance owning `value`, with the class prototype of `ctx`.
+    pub fn new_object<'a>(&self, ctx: &'a Context, value: T) -> Result<JsValue<'a>, Error> {
+        let obj = JsValue::new(ctx, unsafe { JS_NewObjectClass(ctx.inner, self.id as i32) });
+        assert_exception(ctx, &obj, "Could not create class instance")?;
+        obj.set_opaque(Box::into_raw(Box::new(RefCell::new(value))) as _);
+
+        Ok(obj)
+    }
+
+    /// Create an instance owning `value`, with the prototype `proto`.
+    pub fn new_object_with_proto<'a>(
+        &self,
+        ctx: &'a Context,
+        proto: &JsValue,
+        value: T,
+    ) -> Result<JsValue<'a>, Error> {
+        let obj = new_object_proto_class(ctx, proto, self.id)?;
+        obj.set_opaque(Box::into_raw(Box::new(RefCell::new(value))) as _);
+
+        Ok(obj)
+    }
+
+    /// `true` if `val` is an instance of this class.
+    pub fn is_instance(&self, val: &JsValue) -> bool {
+        !unsafe { JS_GetOpaque(val.inner, self.id) }.is_null()
+    }
+}
+
+/// The `RefCell` owned by `val`, which must be an instance of the class registered for `T`.
+fn opaque_cell<'v, T: 'static>(val: &'v JsValue) -> Result<&'v RefCell<T>, Error> {
+    let Some(class) = val.ctx.get_runtime().class_registry().get::<T>() else {
+        Err(Error::BadType(format!(
+            "Class of {} is not registered",
+            type_name::<T>()
+        )))?
+    };
+
+    // JS_GetOpaque 会校验 class id，类型不符时返回 null
+    let opaque = unsafe { JS_GetOpaque(val.inner, class.id) } as *const RefCell<T>;
+    if opaque.is_null() {
+        Err(Error::BadType(format!(
+            "Need {} instance",
+            type_name::<T>()
+        )))?
+    }
+
+    // The opaque lives as long as the object referenced by `val`.
+    Ok(unsafe { &*opaque })
+}
+
+impl<'a> JsValue<'a> {
+    /// Borrow the `T` owned by this object, failing if it is not an instance of the class of
+    /// `T` or if it is mutably borrowed.
+    pub fn borrow_opaque<T: 'static>(&self) -> Result<Ref<'_, T>, Error> {
+        opaque_cell::<T>(self)?.try_borrow().map_err(|_| {
+            Error::GeneralError(format!("{} is already mutably borrowed", type_name::<T>()))
+        })
+    }
+
+    /// Mutably borrow the `T` owned by this object, failing if it is not an instance of the
+    /// class of `T` or if it is already borrowed.
+    pub fn borrow_opaque_mut<T: 'static>(&self) -> Result<RefMut<'_, T>, Error> {
+        opaque_cell::<T>(self)?
+            .try_borrow_mut()
+            .map_err(|_| Error::GeneralError(format!("{} is already borrowed", type_name::<T>())))
+    }
+}
+
+/// A Rust type exposed to scripts as a JS class, implemented by `#[derive(JsClass)]`.
+///
+/// Instances own the Rust value as a `RefCell<Self>` opaque, which is dropped by the class
+/// finalizer.
+pub trait NativeClass: Sized + 'static {
+    /// Name of the JS class and of its constructor.
+    const NAME: &'static str;
+
+    /// Define the accessors of the fields marked with `#[js(get)]` / `#[js(set)]`.
+    fn define_fields(_ctx: &Context, _proto: &JsValue) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Constructor and methods of a [`NativeClass`], implemented by `#[js_methods]`.
+pub trait NativeMethods: NativeClass {
+    /// The `length` of the JS constructor.
+    const CONSTRUCTOR_LENGTH: i32 = 0;
+
+    /// Build the Rust value for `new Class(...args)`.
+    fn constructor(_ctx: &Context, _args: Args) -> Result<Self, Error> {
+        Err(Error::BadType(format!(
+            "{} is not a constructor",
+            Self::NAME
+        )))
+    }
+
+    /// Define the methods and accessors on the class prototype.
+    fn define_methods(_ctx: &Context, _proto: &JsValue) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Define the static methods on the class constructor.
+    fn define_static(_ctx: &Context, _ctor: &JsValue) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn native_constructor<T: NativeMethods>(
+    ctx: *mut JSContext,
+    new_target: JSValue,
+    argc: c_int,
+    argv: *mut JSValue,
+) -> JSValue {
+    Context::with_raw(ctx, |ctx| {
+        catch_callback(ctx, || {
+            let args = Args::from_raw(ctx, argc, argv);
+            let native = T::constructor(ctx, args)?;
+
+            // 从 js ctor 上获取 prototype, 以支持 JS 子类继承
+            let proto = JsValue::new(
+                ctx,
+                JS_GetPropertyStr(ctx.inner, new_target, c"prototype".as_ptr()),
+            );
+            assert_exception(ctx, &proto, "Could not get prototype")?;
+            let class = ctx.get_runtime().register_class::<T>(T::NAME)?;
+
+            class.new_object_with_proto(ctx, &proto, native)
+        })
+    })
+}
+
+/// Register the class `T` in the context and return its constructor.
+pub fn new_native_class<'a, T: NativeMethods>(ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+    // class 注册在 runtime 上，prototype 则每个 context 各有一份
+    let class = ctx.get_runtime().register_class::<T>(T::NAME)?;
+
+    let proto = ctx.new_object()?;
+    T::define_fields(ctx, &proto)?;
+    T::define_methods(ctx, &proto)?;
+
+    let ctor = new_c_function2(
+        ctx,
+        Some(native_constructor::<T>),
+        T::NAME,
+        T::CONSTRUCTOR_LENGTH,
+        true,
+    )?;
+    set_constructor(ctx, &ctor, &proto)?;
+    T::define_static(ctx, &ctor)?;
+
+    // NOTE: JS_SetClassProto takes ownership of the proto.
+    set_class_proto(ctx, class.id(), &proto)?;
+    unsafe { proto.forget() };
+
+    Ok(ctor)
+}
+
+/// Replace the Rust type name in the error of a failed instance check with the class name.
+fn instance_error<T: NativeClass>(err: Error) -> Error {
+    match err {
+        Error::BadType(_) => Error::BadType(format!("Need {} instance", T::NAME)),
+        err => err,
+    }
+}
+
+/// Call `f` with the Rust value of `this`, which must be a `T` instance.
+pub fn with_native<T: NativeClass, R>(this: &JsValue, f: impl FnOnce(&T) -> R) -> Result<R, Error> {
+    let native = this.borrow_opaque::<T>().map_err(instance_error::<T>)?;
+
+    Ok(f(&native))
+}
+
+/// Call `f` with the mutable Rust value of `this`, which must be a `T` instance.
+pub fn with_native_mut<T: NativeClass, R>(
+    this: &JsValue,
+    f: impl FnOnce(&mut T) -> R,
+) -> Result<R, Error> {
+    let mut native = this.borrow_opaque_mut::<T>().map_err(instance_error::<T>)?;
+
+    Ok(f(&mut native))
+}
+
+/// Define a method `name` calling `func` on `obj`, as a writable, configurable property.
+pub fn define_method<F>(ctx: &Context, obj: &JsValue, name: &str, func: F) -> Result<(), Error>
+where
+    F: for<'c> Fn(&'c Context<'c>, JsValue<'c>, Args<'c>) -> Result<JsValue<'c>, Error> + 'static,
+{
+    let func = ctx.new_function(name, func)?;
+    let flags = (JS_PROP_WRITABLE | JS_PROP_CONFIGURABLE) as i32;
+    obj.define_property(name, func, flags)
+}
+
+/// Define an accessor `name` on the prototype of `T` from Rust getter and setter functions.
+pub fn define_native_accessor<T, G, S>(
+    ctx: &Context,
+    proto: &JsValue,
+    name: &str,
+    getter: Option<fn(&T) -> G>,
+    setter: Option<fn(&mut T, S)>,
+) -> Result<(), Error>
+where
+    T: NativeClass,
+    G: for<'c> IntoJs<'c> + 'static,
+    S: for<'c> FromJs<'c> + 'static,
+{
+    let getter = match getter {
+        Some(getter) => Some(ctx.new_function(name, move |ctx, this, _args| {
+            with_native::<T, _>(&this, getter)?.into_js(ctx)
+        })?),
+        None => None,
+    };
+    let setter = match setter {
+        Some(setter) => Some(ctx.new_function(name, move |ctx, this, args| {
+            let val: S = args.get(0)?;
+            with_native_mut::<T, _>(&this, |native| setter(native, val))?;
+            ().into_js(ctx)
+        })?),
+        None => None,
+    };
+
+    let name = ctx.new_atom(name)?;
+    define_property_getset(
+        ctx,
+        proto,
+        name,
+        getter,
+        setter,
+        JS_PROP_CONFIGURABLE as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::{Context, Runtime};
+
+    struct Point {
+        x: i32,
+        dropped: Rc<Cell<bool>>,
+    }
+
+    impl Drop for Point {
+        fn drop(&mut self) {
+            self.dropped.set(true);
+        }
+    }
+
+    #[test]
+    fn test_borrow_opaque() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let dropped = Rc::new(Cell::new(false));
+
+        assert!(!rt.class_registry().contains::<Point>());
+        let class = rt.register_class::<Point>("Point").unwrap();
+        assert_eq!(
+            class.id(),
+            rt.register_class::<Point>("Point").unwrap().id()
+        );
+        assert_eq!(class.id(), rt.class_registry().get::<Point>().unwrap().id());
+
+        let point = Point {
+            x: 1,
+            dropped: dropped.clone(),
+        };
+        let obj = class.new_object(ctx, point).unwrap();
+        assert!(class.is_instance(&obj));
+        assert!(!class.is_instance(&ctx.new_object().unwrap()));
+
+        obj.borrow_opaque_mut::<Point>().unwrap().x += 1;
+        {
+            let p1 = obj.borrow_opaque::<Point>().unwrap();
+            let p2 = obj.borrow_opaque::<Point>().unwrap();
+            assert_eq!((2, 2), (p1.x, p2.x));
+            assert!(obj.borrow_opaque_mut::<Point>().is_err());
+        }
+        {
+            let _p = obj.borrow_opaque_mut::<Point>().unwrap();
+            assert!(obj.borrow_opaque::<Point>().is_err());
+        }
+
+        // 类型不符或未注册的类都会失败
+        assert!(obj.borrow_opaque::<String>().is_err());
+        assert!(ctx.new_object().unwrap().borrow_opaque::<Point>().is_err());
+
+        drop(obj);
+        unsafe { crate::ffi::JS_RunGC(rt.inner) };
+        assert!(dropped.get());
+    }
+}
@@ -1,5 +1,9 @@
+use std::sync::{Arc, Mutex};
+
 use thiserror::Error;
 
+use crate::{Context, JsValue};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("General error: {0}")]
@@ -14,6 +18,19 @@ pub enum Error {
     BadType(String),
     #[error("Value error: {0}")]
     ValueError(String),
+    #[error("Error: Execution interrupted")]
+    Interrupted,
+    #[error("Js exception: {0}")]
+    JsException(Box<JsException>),
+    #[error("JSON parse error: {0}")]
+    JsonParse(JsonParseError),
+    /// Several promises were rejected without handler, see
+    /// [`Runtime::run_jobs_until_idle`](crate::Runtime::run_jobs_until_idle).
+    #[error("{} unhandled promise rejections", .0.len())]
+    UnhandledRejections(Vec<Error>),
+    #[cfg(feature = "serde")]
+    #[error("Serde error: {0}")]
+    Serde(SerdeError),
 }
 
 impl Error {
@@ -23,4 +40,175 @@ impl Error {
         let msg = format!("{msg}({t1}, {t2})");
         Error::BadType(msg)
     }
-}
\ No newline at end of file
+}
+
+/// A JavaScript exception captured from the runtime.
+#[derive(Debug, Clone)]
+pub struct JsException {
+    pub(crate) is_error: bool,
+    pub(crate) name: Option<String>,
+    pub(crate) message: String,
+    pub(crate) stack: Option<String>,
+    pub(crate) cause: Option<String>,
+    pub(crate) properties: Vec<(String, String)>,
+    /// The thrown value kept by the runtime, see [`JsException::value`].
+    pub(crate) value: Arc<ExceptionValue>,
+}
+
+impl PartialEq for JsException {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_error == other.is_error
+            && self.name == other.name
+            && self.message == other.message
+            && self.stack == other.stack
+            && self.cause == other.cause
+            && self.properties == other.properties
+    }
+}
+
+impl Eq for JsException {}
+
+/// Handle of a thrown value kept by the runtime for a [`JsException`] and its clones.
+///
+/// The value is released when the last handle is dropped. Exceptions may be dropped on
+/// another thread, so the key is only queued and the runtime frees the value later.
+pub(crate) struct ExceptionValue {
+    pub(crate) id: u64,
+    pub(crate) released: Arc<Mutex<Vec<u64>>>,
+}
+
+impl std::fmt::Debug for ExceptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ExceptionValue").field(&self.id).finish()
+    }
+}
+
+impl Drop for ExceptionValue {
+    fn drop(&mut self) {
+        let mut released = self.released.lock().unwrap_or_else(|err| err.into_inner());
+        released.push(self.id);
+    }
+}
+
+impl JsException {
+    /// Returns `true` if an `Error` instance was thrown, `false` for throws like `throw 42`.
+    pub fn is_error(&self) -> bool {
+        self.is_error
+    }
+
+    /// The error name (`TypeError`, ...), `None` if the thrown value is not an `Error`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The error message, or the string conversion of a thrown non-`Error` value.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn stack(&self) -> Option<&str> {
+        self.stack.as_deref()
+    }
+
+    pub fn cause(&self) -> Option<&str> {
+        self.cause.as_deref()
+    }
+
+    /// Other own enumerable properties of the thrown object, converted to strings.
+    pub fn properties(&self) -> &[(String, String)] {
+        &self.properties
+    }
+
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, val)| val.as_str())
+    }
+
+    /// The original thrown value, `None` if `ctx` belongs to another runtime than the one
+    /// which captured the exception.
+    pub fn value<'a>(&self, ctx: &'a Context) -> Option<JsValue<'a>> {
+        let raw = ctx.get_runtime().state().exception_value(&self.value)?;
+        let val = JsValue::new(ctx, raw);
+        val.increment_ref_count();
+
+        Some(val)
+    }
+}
+
+/// A syntax error found by [`Context::parse_json`](crate::Context::parse_json).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonParseError {
+    pub(crate) message: String,
+    pub(crate) line: u32,
+    pub(crate) column: Option<u32>,
+}
+
+impl JsonParseError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The 1-based line of the error.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The 1-based column of the error in characters, `None` for extended JSON, where only
+    /// the line is known.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+}
+
+impl std::fmt::Display for JsonParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "{} at line {}, column {column}", self.message, self.line),
+            None => write!(f, "{} at line {}", self.message, self.line),
+        }
+    }
+}
+
+/// An error of [`to_js`](crate::to_js) or [`from_js`](crate::from_js), with the path of
+/// the value which failed.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerdeError {
+    pub(crate) path: String,
+    pub(crate) message: String,
+}
+
+#[cfg(feature = "serde")]
+impl SerdeError {
+    /// Path of the failed value like `items[2].name`, empty for the root value.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at `{}`", self.message, self.path)
+        }
+    }
+}
+
+impl std::fmt::Display for JsException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) if self.message.is_empty() => write!(f, "{name}"),
+            Some(name) => write!(f, "{name}: {}", self.message),
+            None => write!(f, "Uncaught {}", self.message),
+        }
+    }
+}
@@ -1,15 +1,23 @@
+use std::{ffi::c_void, future::Future, mem::ManuallyDrop, time::Duration};
+
 use crate::{
     common::Error,
     ffi::{
-        JSCFunction, JSContext, JSModuleInitFunc, JS_Find_Loaded_Module, JS_FreeContext,
-        JS_FreeRuntime, JS_GetRuntime, JS_NewAtomLen, JS_NewContext, JS_NewObjectWithProto,
+        JSCFunction, JSContext, JSModuleInitFunc, JSValue, JS_DupValue, JS_Find_Loaded_Module,
+        JS_FreeContext, JS_FreeRuntime, JS_FreeValue, JS_GetContextOpaque, JS_GetRuntime,
+        JS_NewAtomLen, JS_NewContext, JS_NewObjectWithProto, JS_SetContextOpaque,
+        JS_EVAL_TYPE_GLOBAL, JS_PARSE_JSON_EXT,
     },
     function::{
-        get_global_object, js_eval, new_atom, new_c_function, new_c_module, new_object_with_proto,
-        new_raw_atom,
+        call_constructor, call_js_function, get_global_object, get_own_property,
+        global_constructor, js_eval, new_array, new_atom, new_c_function, new_c_module,
+        new_function, new_object_with_proto, new_promise_capability, new_raw_atom, parse_json,
+        throw_error, throw_internal_error, throw_range_error, throw_syntax_error, throw_type_error,
+        throw_value,
     },
-    CFunctionInner, JsAtom, JsBoolean, JsInteger, JsModuleDef, JsNumber, JsString, JsValue,
-    Runtime, JS_NULL, JS_UNDEFINED,
+    new_native_class, new_promise_from_future, Args, CFunctionInner, ExecutionLimit, IntoJs,
+    JsArray, JsAtom, JsBoolean, JsFunction, JsInteger, JsModuleDef, JsNumber, JsPromise, JsString,
+    JsValue, ModuleBuilder, NativeMethods, Runtime, JS_NULL, JS_UNDEFINED,
 };
 
 pub struct Context<'a> {
@@ -17,6 +25,112 @@ pub struct Context<'a> {
     pub inner: *mut JSContext,
 }
 
+/// Builtins captured when a context is created, before any script runs. Scripts replacing
+/// the globals or the prototype methods do not change how this crate reads and creates
+/// these objects.
+pub(crate) struct Intrinsics {
+    /// The `Date` constructor.
+    pub(crate) date: JSValue,
+    /// `Date.prototype.getTime`, it throws for values which are not dates.
+    pub(crate) date_get_time: JSValue,
+    pub(crate) map: CollectionIntrinsics,
+    pub(crate) set: CollectionIntrinsics,
+}
+
+/// The constructor and the prototype methods of `Map` or `Set`, the methods check the class
+/// of `this` and throw a `TypeError` for other values.
+pub(crate) struct CollectionIntrinsics {
+    pub(crate) constructor: JSValue,
+    /// `set` of `Map` or `add` of `Set`.
+    pub(crate) set: JSValue,
+    /// `get` of `Map`, undefined for `Set`.
+    pub(crate) get: JSValue,
+    pub(crate) has: JSValue,
+    pub(crate) delete: JSValue,
+    pub(crate) clear: JSValue,
+    /// The getter of `size`.
+    pub(crate) size: JSValue,
+    /// `keys` of `Map` or `values` of `Set`.
+    pub(crate) keys: JSValue,
+    /// `next` of the iterators returned by `keys`.
+    pub(crate) next: JSValue,
+}
+
+fn property<'a>(obj: &JsValue<'a>, name: &str) -> Result<JsValue<'a>, Error> {
+    obj.get_property(name)
+        .ok_or_else(|| Error::PropertyError(format!("{name} is not found")))
+}
+
+impl Intrinsics {
+    fn capture(ctx: &Context) -> Result<Self, Error> {
+        let date = global_constructor(ctx, "Date")?;
+        let date_get_time = property(&property(&date, "prototype")?, "getTime")?;
+
+        Ok(Self {
+            date: unsafe { date.forget() },
+            date_get_time: unsafe { date_get_time.forget() },
+            map: CollectionIntrinsics::capture(ctx, "Map")?,
+            set: CollectionIntrinsics::capture(ctx, "Set")?,
+        })
+    }
+
+    unsafe fn free(self, ctx: *mut JSContext) {
+        JS_FreeValue(ctx, self.date);
+        JS_FreeValue(ctx, self.date_get_time);
+        self.map.free(ctx);
+        self.set.free(ctx);
+    }
+}
+
+impl CollectionIntrinsics {
+    fn capture(ctx: &Context, name: &str) -> Result<Self, Error> {
+        let is_set = name == "Set";
+        let constructor = global_constructor(ctx, name)?;
+        let proto = property(&constructor, "prototype")?;
+        let size = get_own_property(ctx, &proto, &new_atom(ctx, "size")?)?
+            .and_then(|desc| desc.getter)
+            .ok_or_else(|| Error::PropertyError(format!("{name}.prototype.size is not found")))?;
+        let keys = property(&proto, if is_set { "values" } else { "keys" })?;
+        // next 位于迭代器的原型上，从一个空集合的迭代器取得
+        let empty = call_constructor(ctx, &constructor, &[])?;
+        let next = property(&call_js_function(ctx, &keys, Some(&empty), &[])?, "next")?;
+        let get = if is_set {
+            JsValue::new(ctx, JS_UNDEFINED)
+        } else {
+            property(&proto, "get")?
+        };
+
+        Ok(Self {
+            set: unsafe { property(&proto, if is_set { "add" } else { "set" })?.forget() },
+            get: unsafe { get.forget() },
+            has: unsafe { property(&proto, "has")?.forget() },
+            delete: unsafe { property(&proto, "delete")?.forget() },
+            clear: unsafe { property(&proto, "clear")?.forget() },
+            size: unsafe { size.forget() },
+            keys: unsafe { keys.forget() },
+            next: unsafe { next.forget() },
+            constructor: unsafe { constructor.forget() },
+        })
+    }
+
+    unsafe fn free(self, ctx: *mut JSContext) {
+        let values = [
+            self.constructor,
+            self.set,
+            self.get,
+            self.has,
+            self.delete,
+            self.clear,
+            self.size,
+            self.keys,
+            self.next,
+        ];
+        for val in values {
+            JS_FreeValue(ctx, val);
+        }
+    }
+}
+
 impl<'a> Context<'a> {
     pub fn new(runtime: &'a Runtime) -> Self {
         let inner = unsafe { JS_NewContext(runtime.inner) };
@@ -24,7 +138,43 @@ impl<'a> Context<'a> {
             panic!("Context create failed");
         }
 
-        Self { runtime, inner }
+        let ctx = Self { runtime, inner };
+        let intrinsics = Intrinsics::capture(&ctx).expect("Context create failed");
+        unsafe { JS_SetContextOpaque(inner, Box::into_raw(Box::new(intrinsics)) as *mut c_void) };
+
+        ctx
+    }
+
+    /// The builtin picked by `pick` from the ones captured when the context was created.
+    pub(crate) fn intrinsic(
+        &self,
+        pick: impl FnOnce(&Intrinsics) -> JSValue,
+    ) -> Result<JsValue<'_>, Error> {
+        let intrinsics = unsafe { JS_GetContextOpaque(self.inner) } as *const Intrinsics;
+        if intrinsics.is_null() {
+            Err(Error::GeneralError(
+                "The context was not created by Context::new".to_owned(),
+            ))?
+        }
+
+        let val = pick(unsafe { &*intrinsics });
+        unsafe { JS_DupValue(self.inner, val) };
+        Ok(JsValue::new(self, val))
+    }
+
+    /// Borrow a raw context inside a native callback, the context and its runtime are not
+    /// freed when `f` returns.
+    ///
+    /// # Safety
+    /// `ctx` must be a valid context created by a [`Runtime`] of this crate.
+    pub unsafe fn with_raw<R>(ctx: *mut JSContext, f: impl FnOnce(&Context) -> R) -> R {
+        let runtime = ManuallyDrop::new(Runtime::from_raw(JS_GetRuntime(ctx)));
+        let ctx = ManuallyDrop::new(Context {
+            runtime: &runtime,
+            inner: ctx,
+        });
+
+        f(&ctx)
     }
 
     pub fn new_module(
@@ -69,6 +219,10 @@ impl<'a> Context<'a> {
         new_object_with_proto(self, Some(proto))
     }
 
+    pub fn new_array(&self) -> Result<JsArray<'_>, Error> {
+        new_array(self)
+    }
+
     pub fn new_atom(&self, name: &str) -> Result<JsAtom, Error> {
         new_atom(self, name)
     }
@@ -82,6 +236,57 @@ impl<'a> Context<'a> {
         js_eval(self, code, file_name, eval_flags)
     }
 
+    /// Evaluate a global script, interrupting it with [`Error::Interrupted`] once `timeout` elapsed.
+    pub fn eval_with_timeout(
+        &'a self,
+        code: &str,
+        timeout: Duration,
+    ) -> Result<JsValue<'a>, Error> {
+        self.eval_with_limit(
+            code,
+            "<eval>",
+            JS_EVAL_TYPE_GLOBAL as i32,
+            ExecutionLimit::Timeout(timeout),
+        )
+    }
+
+    /// Evaluate a global script, interrupting it with [`Error::Interrupted`] once it ran
+    /// roughly `instructions` instructions.
+    pub fn eval_with_budget(&'a self, code: &str, instructions: u64) -> Result<JsValue<'a>, Error> {
+        self.eval_with_limit(
+            code,
+            "<eval>",
+            JS_EVAL_TYPE_GLOBAL as i32,
+            ExecutionLimit::Instructions(instructions),
+        )
+    }
+
+    pub fn eval_with_limit(
+        &'a self,
+        code: &str,
+        file_name: &str,
+        eval_flags: i32,
+        limit: ExecutionLimit,
+    ) -> Result<JsValue<'a>, Error> {
+        self.runtime
+            .with_execution_limit(limit, || js_eval(self, code, file_name, eval_flags))
+    }
+
+    /// Parse the strict JSON text `json`.
+    pub fn parse_json(&self, json: &str) -> Result<JsValue<'_>, Error> {
+        parse_json(self, json, 0)
+    }
+
+    /// Parse `json`, accepting the extended JSON syntax of QuickJS if `extended` is `true`.
+    pub fn parse_json_with(&self, json: &str, extended: bool) -> Result<JsValue<'_>, Error> {
+        let flags = if extended {
+            JS_PARSE_JSON_EXT as i32
+        } else {
+            0
+        };
+        parse_json(self, json, flags)
+    }
+
     pub fn get_number(&self, val: f64) -> JsValue {
         JsNumber::new(self, val).into()
     }
@@ -111,6 +316,66 @@ impl<'a> Context<'a> {
         new_c_function(self, Some(c_func), name, arg_count)
     }
 
+    /// Expose a Rust closure to scripts as a JS function, see [`new_function`].
+    pub fn new_function<F>(&self, name: &str, func: F) -> Result<JsValue<'_>, Error>
+    where
+        F: for<'c> Fn(&'c Context<'c>, JsValue<'c>, Args<'c>) -> Result<JsValue<'c>, Error>
+            + 'static,
+    {
+        new_function(self, name, func)
+    }
+
+    /// Create a pending promise and its `resolve` and `reject` functions.
+    pub fn new_promise(&self) -> Result<(JsPromise<'_>, JsFunction<'_>, JsFunction<'_>), Error> {
+        new_promise_capability(self)
+    }
+
+    /// Return `future` to scripts as a promise, see [`new_promise_from_future`].
+    pub fn new_promise_from_future<F, V>(&self, future: F) -> Result<JsPromise<'_>, Error>
+    where
+        F: Future<Output = Result<V, Error>> + 'static,
+        V: for<'c> IntoJs<'c> + 'static,
+    {
+        new_promise_from_future(self, future)
+    }
+
+    /// Register the class `T` and define its constructor on the global object.
+    pub fn register_class<T: NativeMethods>(&self) -> Result<(), Error> {
+        let ctor = new_native_class::<T>(self)?;
+        self.get_global_object().set_property(T::NAME, ctor)
+    }
+
+    /// Throw a `TypeError`, returns `JS_EXCEPTION` so native callbacks can return it directly.
+    pub fn throw_type_error(&self, msg: &str) -> JSValue {
+        throw_type_error(self, msg)
+    }
+
+    pub fn throw_range_error(&self, msg: &str) -> JSValue {
+        throw_range_error(self, msg)
+    }
+
+    pub fn throw_syntax_error(&self, msg: &str) -> JSValue {
+        throw_syntax_error(self, msg)
+    }
+
+    pub fn throw_internal_error(&self, msg: &str) -> JSValue {
+        throw_internal_error(self, msg)
+    }
+
+    pub fn throw_value(&self, val: JsValue) -> JSValue {
+        throw_value(self, val)
+    }
+
+    /// Throw a Rust error as a JS `Error` object, see [`throw_error`].
+    pub fn throw_error(&self, err: Error) -> JSValue {
+        throw_error(self, err)
+    }
+
+    /// Start building a native ES module named `name`.
+    pub fn module(&'a self, name: &str) -> ModuleBuilder<'a> {
+        ModuleBuilder::new(self, name)
+    }
+
     pub fn find_loaded_module(&self, module_name: &str) -> JsModuleDef {
         let atom = new_raw_atom(self, module_name);
         let m = unsafe { JS_Find_Loaded_Module(self.inner, atom) };
@@ -120,6 +385,12 @@ impl<'a> Context<'a> {
 
 impl<'a> Drop for Context<'a> {
     fn drop(&mut self) {
-        unsafe { JS_FreeContext(self.inner) }
+        unsafe {
+            let intrinsics = JS_GetContextOpaque(self.inner) as *mut Intrinsics;
+            if !intrinsics.is_null() {
+                Box::from_raw(intrinsics).free(self.inner);
+            }
+            JS_FreeContext(self.inner)
+        }
     }
 }
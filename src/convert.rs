@@ -0,0 +1,526 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hash},
+    time::SystemTime,
+};
+
+#[cfg(feature = "bigint")]
+use crate::JsBigInt;
+use crate::{
+    common::{make_cstring, Error},
+    ffi::{
+        JS_GetPropertyStr, JS_GetPropertyUint32, JS_NewArray, JS_NewBool, JS_NewFloat64,
+        JS_NewInt32, JS_SetPropertyUint32, JS_ToBoolean, JS_ToF64, JS_GPN_ENUM_ONLY,
+        JS_GPN_STRING_MASK,
+    },
+    function::{assert_exception, get_last_exception, own_property_names, to_string_lossy},
+    Context, JsArray, JsArrayBuffer, JsBoolean, JsDate, JsFunction, JsInteger, JsMap, JsNumber,
+    JsObject, JsPromise, JsSet, JsString, JsSymbol, JsTypedArray, JsValue, TypedArrayElement,
+    JS_UNDEFINED,
+};
+
+/// Conversion from a JS value to a Rust value.
+pub trait FromJs<'a>: Sized {
+    fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error>;
+}
+
+/// Conversion from a Rust value to a JS value.
+pub trait IntoJs<'a> {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error>;
+}
+
+/// Arguments of a JS function call, implemented for tuples, arrays and `Vec` of [`IntoJs`] values.
+pub trait IntoJsArgs<'a> {
+    fn into_js_args(self, ctx: &'a Context) -> Result<Vec<JsValue<'a>>, Error>;
+}
+
+fn type_error<T>(value: &JsValue) -> Error {
+    Error::BadType(format!(
+        "Need {} but get {:?}",
+        std::any::type_name::<T>(),
+        value.tag()
+    ))
+}
+
+fn number_value<T>(value: &JsValue) -> Result<f64, Error> {
+    if !value.is_number() {
+        Err(type_error::<T>(value))?
+    }
+
+    Ok(JS_ToF64(value.ctx.inner, value.inner))
+}
+
+fn array_length(value: &JsValue) -> Result<u32, Error> {
+    let len = unsafe { JS_GetPropertyStr(value.ctx.inner, value.inner, c"length".as_ptr()) };
+    let len = JsValue::new(value.ctx, len);
+    assert_exception(value.ctx, &len, "Could not get array length")?;
+
+    u32::from_js(value.ctx, len)
+}
+
+fn array_item<'a>(value: &JsValue<'a>, idx: u32) -> Result<JsValue<'a>, Error> {
+    let item = unsafe { JS_GetPropertyUint32(value.ctx.inner, value.inner, idx) };
+    let item = JsValue::new(value.ctx, item);
+    assert_exception(value.ctx, &item, "Could not get array item")?;
+
+    Ok(item)
+}
+
+fn new_array<'a>(ctx: &'a Context, items: Vec<JsValue<'a>>) -> Result<JsValue<'a>, Error> {
+    let arr = JsValue::new(ctx, unsafe { JS_NewArray(ctx.inner) });
+    assert_exception(ctx, &arr, "Could not create array")?;
+
+    for (idx, item) in items.into_iter().enumerate() {
+        // NOTE: JS_SetPropertyUint32 takes ownership of the value.
+        let rst = unsafe { JS_SetPropertyUint32(ctx.inner, arr.inner, idx as u32, item.forget()) };
+        if rst < 0 {
+            Err(get_last_exception(ctx)
+                .unwrap_or_else(|| Error::PropertyError("Could not set array item".into())))?
+        }
+    }
+
+    Ok(arr)
+}
+
+impl<'a> FromJs<'a> for JsValue<'a> {
+    fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl<'a> IntoJs<'a> for JsValue<'a> {
+    fn into_js(self, _ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        Ok(self)
+    }
+}
+
+macro_rules! impl_wrapper_convert {
+    ($($type:ident),*) => {
+        $(
+            impl<'a> FromJs<'a> for $type<'a> {
+                fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+                    value.try_into()
+                }
+            }
+
+            impl<'a> IntoJs<'a> for $type<'a> {
+                fn into_js(self, _ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+                    Ok(self.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_wrapper_convert!(
+    JsInteger, JsNumber, JsBoolean, JsString, JsObject, JsArray, JsFunction, JsPromise, JsSymbol
+);
+#[cfg(feature = "bigint")]
+impl_wrapper_convert!(JsBigInt);
+impl_wrapper_convert!(JsArrayBuffer, JsDate, JsMap, JsSet);
+
+impl<'a, T: TypedArrayElement> FromJs<'a> for JsTypedArray<'a, T> {
+    fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        value.try_into()
+    }
+}
+
+impl<'a, T: TypedArrayElement> IntoJs<'a> for JsTypedArray<'a, T> {
+    fn into_js(self, _ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        Ok(self.into())
+    }
+}
+
+macro_rules! impl_int_convert {
+    ($($type:ty),*) => {
+        $(
+            impl<'a> FromJs<'a> for $type {
+                fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+                    let val = number_value::<$type>(&value)?;
+                    // MIN 和 MAX + 1 都是 2 的幂，可以精确表示 (64 位类型的 MAX as f64 本身
+                    // 就已向上舍入为 MAX + 1)，所以上界不能包含在内
+                    let upper = <$type>::MAX as f64 + 1.0;
+                    if val.fract() != 0.0 || val < <$type>::MIN as f64 || val >= upper {
+                        Err(Error::ValueError(format!(
+                            "{val} can not be converted to {}",
+                            stringify!($type)
+                        )))?
+                    }
+
+                    Ok(val as $type)
+                }
+            }
+
+            impl<'a> IntoJs<'a> for $type {
+                fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+                    // 超出 i32 范围的整数只能以 Float64 表示
+                    let val = match i32::try_from(self) {
+                        Ok(val) => unsafe { JS_NewInt32(ctx.inner, val) },
+                        Err(_) => unsafe { JS_NewFloat64(ctx.inner, self as f64) },
+                    };
+
+                    Ok(JsValue::new(ctx, val))
+                }
+            }
+        )*
+    };
+}
+
+impl_int_convert!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_float_convert {
+    ($($type:ty),*) => {
+        $(
+            impl<'a> FromJs<'a> for $type {
+                fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+                    Ok(number_value::<$type>(&value)? as $type)
+                }
+            }
+
+            impl<'a> IntoJs<'a> for $type {
+                fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+                    Ok(JsValue::new(ctx, unsafe { JS_NewFloat64(ctx.inner, self as f64) }))
+                }
+            }
+        )*
+    };
+}
+
+impl_float_convert!(f32, f64);
+
+impl<'a> FromJs<'a> for bool {
+    fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        if !value.is_bool() {
+            Err(type_error::<bool>(&value))?
+        }
+
+        Ok(JS_ToBoolean(ctx.inner, value.inner))
+    }
+}
+
+impl<'a> IntoJs<'a> for bool {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        Ok(JsValue::new(ctx, unsafe { JS_NewBool(ctx.inner, self) }))
+    }
+}
+
+impl<'a> FromJs<'a> for String {
+    fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        if !value.is_string() {
+            Err(type_error::<String>(&value))?
+        }
+
+        to_string_lossy(ctx, value.inner).ok_or_else(|| {
+            get_last_exception(ctx)
+                .unwrap_or_else(|| Error::ValueError("Could not convert string".to_owned()))
+        })
+    }
+}
+
+impl<'a> IntoJs<'a> for String {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        self.as_str().into_js(ctx)
+    }
+}
+
+impl<'a> IntoJs<'a> for &str {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        let val = JsValue::from(JsString::new(ctx, self));
+        assert_exception(ctx, &val, "Could not create string")?;
+
+        Ok(val)
+    }
+}
+
+/// Converted from a valid `Date`, precision below a millisecond is truncated.
+impl<'a> FromJs<'a> for SystemTime {
+    fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        value.to_date()?.to_system_time()
+    }
+}
+
+impl<'a> IntoJs<'a> for SystemTime {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        Ok(JsDate::new(ctx, self)?.into())
+    }
+}
+
+impl<'a> FromJs<'a> for () {
+    fn from_js(_ctx: &'a Context, _value: JsValue<'a>) -> Result<Self, Error> {
+        Ok(())
+    }
+}
+
+impl<'a> IntoJs<'a> for () {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        Ok(JsValue::new(ctx, JS_UNDEFINED))
+    }
+}
+
+/// `null` and `undefined` are converted to `None`, `None` is converted to `undefined`.
+impl<'a, T: FromJs<'a>> FromJs<'a> for Option<T> {
+    fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        if value.is_null() || value.is_undefined() {
+            Ok(None)
+        } else {
+            T::from_js(ctx, value).map(Some)
+        }
+    }
+}
+
+impl<'a, T: IntoJs<'a>> IntoJs<'a> for Option<T> {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        match self {
+            Some(val) => val.into_js(ctx),
+            None => Ok(JsValue::new(ctx, JS_UNDEFINED)),
+        }
+    }
+}
+
+impl<'a, T: FromJs<'a>> FromJs<'a> for Vec<T> {
+    fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        if !value.is_array() {
+            Err(type_error::<Self>(&value))?
+        }
+
+        (0..array_length(&value)?)
+            .map(|idx| T::from_js(ctx, array_item(&value, idx)?))
+            .collect()
+    }
+}
+
+impl<'a, T: IntoJs<'a>> IntoJs<'a> for Vec<T> {
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        let items = self
+            .into_iter()
+            .map(|item| item.into_js(ctx))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        new_array(ctx, items)
+    }
+}
+
+/// Converted from the entries of a `Map`, or from the own enumerable string keyed properties
+/// of other objects, the property names are converted to `K` from JS strings.
+impl<'a, K, T, S> FromJs<'a> for HashMap<K, T, S>
+where
+    K: FromJs<'a> + Eq + Hash,
+    T: FromJs<'a>,
+    S: BuildHasher + Default,
+{
+    fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        if value.is_map() {
+            return value.to_map()?.to_hash_map();
+        }
+        if !value.is_object() {
+            Err(type_error::<Self>(&value))?
+        }
+
+        own_property_names(ctx, &value, JS_GPN_STRING_MASK | JS_GPN_ENUM_ONLY)
+            .into_iter()
+            .map(|name| {
+                let cname = make_cstring(name.as_str())?;
+                let val = unsafe { JS_GetPropertyStr(ctx.inner, value.inner, cname.as_ptr()) };
+                let val = JsValue::new(ctx, val);
+                assert_exception(ctx, &val, "Could not get property")?;
+
+                let key = K::from_js(ctx, name.as_str().into_js(ctx)?)?;
+                Ok((key, T::from_js(ctx, val)?))
+            })
+            .collect()
+    }
+}
+
+impl<'a, K, T, S> IntoJs<'a> for HashMap<K, T, S>
+where
+    K: AsRef<str> + Eq + Hash,
+    T: IntoJs<'a>,
+{
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        let obj = ctx.new_object()?;
+        for (key, val) in self {
+            obj.set_property(key.as_ref(), val.into_js(ctx)?)?;
+        }
+
+        Ok(obj)
+    }
+}
+
+/// Converted from the values of a `Set`.
+impl<'a, T, S> FromJs<'a> for HashSet<T, S>
+where
+    T: FromJs<'a> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_js(_ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+        if !value.is_set() {
+            Err(type_error::<Self>(&value))?
+        }
+
+        value.to_set()?.to_hash_set()
+    }
+}
+
+impl<'a, T, S> IntoJs<'a> for HashSet<T, S>
+where
+    T: IntoJs<'a> + Eq + Hash,
+{
+    fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+        JsSet::from_values(ctx, self).map(JsValue::from)
+    }
+}
+
+macro_rules! impl_tuple_convert {
+    ($len:expr; $($name:ident $idx:tt),+) => {
+        /// Converted from an array with at least as many items as the tuple.
+        impl<'a, $($name: FromJs<'a>),+> FromJs<'a> for ($($name,)+) {
+            fn from_js(ctx: &'a Context, value: JsValue<'a>) -> Result<Self, Error> {
+                if !value.is_array() || array_length(&value)? < $len {
+                    Err(type_error::<Self>(&value))?
+                }
+
+                Ok(($($name::from_js(ctx, array_item(&value, $idx)?)?,)+))
+            }
+        }
+
+        impl<'a, $($name: IntoJs<'a>),+> IntoJs<'a> for ($($name,)+) {
+            fn into_js(self, ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+                new_array(ctx, self.into_js_args(ctx)?)
+            }
+        }
+
+        impl<'a, $($name: IntoJs<'a>),+> IntoJsArgs<'a> for ($($name,)+) {
+            fn into_js_args(self, ctx: &'a Context) -> Result<Vec<JsValue<'a>>, Error> {
+                Ok(vec![$(self.$idx.into_js(ctx)?),+])
+            }
+        }
+    };
+}
+
+impl_tuple_convert!(1; A 0);
+impl_tuple_convert!(2; A 0, B 1);
+impl_tuple_convert!(3; A 0, B 1, C 2);
+impl_tuple_convert!(4; A 0, B 1, C 2, D 3);
+impl_tuple_convert!(5; A 0, B 1, C 2, D 3, E 4);
+impl_tuple_convert!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple_convert!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple_convert!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl<'a> IntoJsArgs<'a> for () {
+    fn into_js_args(self, _ctx: &'a Context) -> Result<Vec<JsValue<'a>>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+impl<'a, T: IntoJs<'a>> IntoJsArgs<'a> for Vec<T> {
+    fn into_js_args(self, ctx: &'a Context) -> Result<Vec<JsValue<'a>>, Error> {
+        self.into_iter().map(|arg| arg.into_js(ctx)).collect()
+    }
+}
+
+impl<'a, T: IntoJs<'a>, const N: usize> IntoJsArgs<'a> for [T; N] {
+    fn into_js_args(self, ctx: &'a Context) -> Result<Vec<JsValue<'a>>, Error> {
+        self.into_iter().map(|arg| arg.into_js(ctx)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{common::Error, Context, Runtime};
+
+    use super::*;
+
+    fn round_trip<'a, T>(ctx: &'a Context, val: T) -> T
+    where
+        T: FromJs<'a> + IntoJs<'a>,
+    {
+        let js_val = val.into_js(ctx).unwrap();
+        T::from_js(ctx, js_val).unwrap()
+    }
+
+    #[test]
+    fn test_convert() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        assert_eq!(-7_i8, round_trip(ctx, -7_i8));
+        assert_eq!(u32::MAX, round_trip(ctx, u32::MAX));
+        assert_eq!(1_i64 << 40, round_trip(ctx, 1_i64 << 40));
+        assert_eq!(1.5_f32, round_trip(ctx, 1.5_f32));
+        assert!(round_trip(ctx, true));
+        assert_eq!("abc", round_trip(ctx, "abc".to_owned()));
+        assert_eq!(None, round_trip(ctx, None::<i32>));
+        assert_eq!(vec![1, 2, 3], round_trip(ctx, vec![1, 2, 3]));
+        assert_eq!(
+            (1, "a".to_owned(), false),
+            round_trip(ctx, (1, "a".to_owned(), false))
+        );
+
+        let map = HashMap::from([("a".to_owned(), 1.5), ("b".to_owned(), 2.0)]);
+        assert_eq!(map, round_trip(ctx, map.clone()));
+
+        let val = ctx.eval("1.5", "<test>", flags).unwrap();
+        assert!(matches!(i32::from_js(ctx, val), Err(Error::ValueError(_))));
+        let val = ctx.eval("300", "<test>", flags).unwrap();
+        assert!(matches!(u8::from_js(ctx, val), Err(Error::ValueError(_))));
+        let val = ctx.eval("'1'", "<test>", flags).unwrap();
+        assert!(matches!(i32::from_js(ctx, val), Err(Error::BadType(_))));
+
+        // 范围边界
+        let num = |code: &str| ctx.eval(code, "<test>", flags).unwrap();
+        assert_eq!(255, u8::from_js(ctx, num("255")).unwrap());
+        assert!(u8::from_js(ctx, num("256")).is_err());
+        assert_eq!(i32::MIN, i32::from_js(ctx, num("-(2 ** 31)")).unwrap());
+        assert!(i32::from_js(ctx, num("2 ** 31")).is_err());
+        assert_eq!(i64::MIN, i64::from_js(ctx, num("-(2 ** 63)")).unwrap());
+        assert!(i64::from_js(ctx, num("2 ** 63")).is_err());
+        assert_eq!(1 << 63, u64::from_js(ctx, num("2 ** 63")).unwrap());
+        assert_eq!(
+            u64::MAX - 2047,
+            u64::from_js(ctx, num("2 ** 64 - 2048")).unwrap()
+        );
+        assert!(u64::from_js(ctx, num("2 ** 64")).is_err());
+        assert!(u64::from_js(ctx, num("-1")).is_err());
+
+        let val = ctx
+            .eval("({ a: [1, null], b: [] })", "<test>", flags)
+            .unwrap();
+        let rst: HashMap<String, Vec<Option<u8>>> = FromJs::from_js(ctx, val).unwrap();
+        assert_eq!(vec![Some(1), None], rst["a"]);
+        assert!(rst["b"].is_empty());
+    }
+
+    #[test]
+    fn test_call() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let js_fn = ctx
+            .eval("(a, b, c) => `${a}:${b}:${c}`", "<test>", flags)
+            .unwrap()
+            .to_function()
+            .unwrap();
+        let rst: String = js_fn.call_as((1, "x", Some(true))).unwrap();
+        assert_eq!("1:x:true", rst);
+        let rst: String = js_fn.call_as([1.5, 2.5]).unwrap();
+        assert_eq!("1.5:2.5:undefined", rst);
+        js_fn.call_as::<(), _>(()).unwrap();
+
+        let sum = ctx
+            .new_function("sum", |ctx, _this, args| {
+                let mut sum = 0;
+                for arg in args {
+                    sum += i64::from_js(ctx, arg.clone())?;
+                }
+                sum.into_js(ctx)
+            })
+            .unwrap()
+            .to_function()
+            .unwrap();
+        assert_eq!(6_i64, sum.call_as(vec![1, 2, 3]).unwrap());
+    }
+}
@@ -1,15 +1,28 @@
-use std::{borrow::Cow, ffi::c_void, ptr::null_mut};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::c_void,
+    hash::{BuildHasher, Hash},
+    ptr::null_mut,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
     common::{make_cstring, Error},
     ffi::{
-        Find_Export_Entry, JSContext, JSExportEntry, JSRefCountHeader, JSValue, JSValueUnion,
-        JS_AtomToString, JS_DupValue, JS_FreeValue, JS_NewAtomLen, JS_NewFloat64, JS_NewInt32,
-        JS_NewString, JS_ToF64, JS_ToI32, JS_ToStr, JS_ATOM_NULL, JS_MKVAL, JS_TAG_EXCEPTION,
-        JS_TAG_NULL, JS_TAG_UNDEFINED,
+        Find_Export_Entry, JSContext, JSExportEntry, JSPromiseStateEnum_JS_PROMISE_FULFILLED,
+        JSPromiseStateEnum_JS_PROMISE_REJECTED, JSRefCountHeader, JSValue, JSValueUnion,
+        JS_AtomToString, JS_DupValue, JS_FreeValue, JS_GetPropertyStr, JS_GetPropertyUint32,
+        JS_NewAtomLen, JS_NewFloat64, JS_NewInt32, JS_NewString, JS_PromiseResult, JS_PromiseState,
+        JS_SetPropertyUint32, JS_ToF64, JS_ToI32, JS_ToStr, JS_ATOM_NULL, JS_GPN_ENUM_ONLY,
+        JS_GPN_STRING_MASK, JS_GPN_SYMBOL_MASK, JS_MKVAL, JS_TAG_EXCEPTION, JS_TAG_NULL,
+        JS_TAG_UNDEFINED,
+    },
+    function::{
+        assert_exception, call_js_function, get_last_exception, new_exception,
+        run_compiled_function, to_bytecode, MapMethod,
     },
-    function::{get_last_exception, run_compiled_function, to_bytecode},
-    Context,
+    Context, FromJs, IntoJs, IntoJsArgs, IntoPropertyKey, PropertyKey,
 };
 
 pub type JSCGetter =
@@ -17,7 +30,6 @@ pub type JSCGetter =
 pub type JSCSetter =
     Option<unsafe extern "C" fn(ctx: *mut JSContext, this_val: JSValue, val: JSValue) -> JSValue>;
 
-pub const JS_CLASS_PROMISE: u32 = 45;
 pub const JS_NULL: JSValue = JS_MKVAL(JS_TAG_NULL, 0);
 pub const JS_NULL_PTR: *mut crate::ffi::JSValue = std::ptr::null_mut();
 pub const NULL_SIZE: *mut usize = std::ptr::null_mut();
@@ -254,7 +266,6 @@ pub enum JsTag {
     Object = crate::ffi::JS_TAG_OBJECT,
     String = crate::ffi::JS_TAG_STRING,
     Symbol = crate::ffi::JS_TAG_SYMBOL,
-    #[cfg(feature = "bigint")]
     BigInt = crate::ffi::JS_TAG_BIG_INT,
     Float64 = crate::ffi::JS_TAG_FLOAT64,
     BigFloat = crate::ffi::JS_TAG_BIG_FLOAT,
@@ -286,8 +297,7 @@ impl JsTag {
             crate::ffi::JS_TAG_CATCH_OFFSET => JsTag::CatchOffset,
             crate::ffi::JS_TAG_UNINITIALIZED => JsTag::Uninitialized,
             crate::ffi::JS_TAG_FUNCTION_BYTECODE => JsTag::FunctionBytecode,
-            #[cfg(feature = "bigint")]
-            ffi::JS_TAG_BIG_INT => JsTag::BigInt,
+            crate::ffi::JS_TAG_BIG_INT => JsTag::BigInt,
             _other => {
                 unreachable!()
             }
@@ -313,8 +323,7 @@ impl JsTag {
             JsTag::CatchOffset => crate::ffi::JS_TAG_CATCH_OFFSET,
             JsTag::Uninitialized => crate::ffi::JS_TAG_UNINITIALIZED,
             JsTag::FunctionBytecode => crate::ffi::JS_TAG_FUNCTION_BYTECODE,
-            #[cfg(feature = "bigint")]
-            JsTag::BigInt => crate::ffi::JS_TAG_FUNCTION_BYTECODE,
+            JsTag::BigInt => crate::ffi::JS_TAG_BIG_INT,
         }
     }
 
@@ -379,7 +388,6 @@ impl JsTag {
     }
 
     /// Returns `true` if the js_tag is [`BigInt`].
-    #[cfg(feature = "bigint")]
     #[inline]
     pub fn is_big_int(&self) -> bool {
         matches!(self, Self::BigInt)
@@ -479,6 +487,12 @@ impl<'a> Drop for JsAtom<'a> {
     }
 }
 
+impl<'a> std::fmt::Debug for JsAtom<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsAtom").field(&self.inner).finish()
+    }
+}
+
 impl<'a> Clone for JsAtom<'a> {
     fn clone(&self) -> Self {
         unsafe { crate::ffi::JS_DupAtom(self.ctx.inner, self.inner) };
@@ -600,59 +614,35 @@ impl<'a> JsValue<'a> {
         self.inner
     }
 
-    pub fn set_property(&self, prop_name: &str, prop_value: JsValue) -> Result<(), Error> {
-        let p_name = make_cstring(prop_name)?;
-
-        let val = unsafe {
-            crate::ffi::JS_SetPropertyStr(
-                self.ctx.inner,
-                self.inner,
-                p_name.as_ptr(),
-                prop_value.dup_value(),
-                // prop_value.inner,
-            )
-        };
-
-        if val == -1 {
-            Err(Error::GeneralError(format!(
-                "Set property '{}' for object is failed",
-                prop_name
-            )))?;
-        }
-
-        // unsafe { prop_value.forget() };
-
-        Ok(())
+    /// Set the property `key`, which is an index, a name, an atom or a symbol.
+    pub fn set_property<K: IntoPropertyKey<'a>>(
+        &self,
+        key: K,
+        prop_value: JsValue,
+    ) -> Result<(), Error> {
+        let key = key.into_key(self.ctx)?;
+        crate::function::set_property(self.ctx, self, &key, prop_value)
     }
 
-    pub fn get_property(&self, prop_name: &str) -> Option<JsValue> {
-        let p_name = {
-            if let Ok(val) = make_cstring(prop_name) {
-                val
-            } else {
-                return None;
-            }
-        };
-
-        let val =
-            unsafe { crate::ffi::JS_GetPropertyStr(self.ctx.inner, self.inner, p_name.as_ptr()) };
-
-        let val = JsValue::new(self.ctx, val);
+    /// Get the property `key`, `None` if it is undefined or the getter threw.
+    pub fn get_property<K: IntoPropertyKey<'a>>(&self, key: K) -> Option<JsValue<'a>> {
+        let key = key.into_key(self.ctx).ok()?;
+        let val = crate::function::get_property(self.ctx, self, &key).ok()?;
 
-        if val.is_exception() || val.is_undefined() {
+        if val.is_undefined() {
             None
         } else {
             Some(val)
         }
     }
 
-    pub fn define_property(
+    pub fn define_property<K: IntoPropertyKey<'a>>(
         &self,
-        prop_name: &str,
+        key: K,
         prop_value: JsValue,
         flags: i32,
     ) -> Result<(), Error> {
-        let prop_name = self.ctx.new_atom(prop_name)?;
+        let prop_name = key.into_key(self.ctx)?.to_atom(self.ctx)?;
         crate::function::define_property(self.ctx, self, prop_name, prop_value, flags)
     }
 
@@ -670,16 +660,105 @@ impl<'a> JsValue<'a> {
     // is_fn!(is_module);
     is_fn!(is_string);
     is_fn!(is_symbol);
+    is_fn!(is_big_int);
     is_fn!(is_float64);
     is_fn!(is_big_float);
     is_fn!(is_big_decimal);
     is_fn!(is_compiled_function);
 
+    /// Returns `true` if the value is a typed array with elements of `T`, like a `Uint8Array`
+    /// for `u8`.
+    pub fn is_typed_array<T: TypedArrayElement>(&self) -> bool {
+        typed_array_constructor(self.inner) == Some(T::CONSTRUCTOR)
+    }
+
+    /// Returns `true` if the value is a `Date` object, objects only inheriting from
+    /// `Date.prototype` are not dates.
+    pub fn is_date(&self) -> bool {
+        matches!(
+            crate::function::date_time_value(self.ctx, self),
+            Ok(Some(_))
+        )
+    }
+
+    pub fn to_date(self) -> Result<JsDate<'a>, Error> {
+        if !self.is_date() {
+            Err(Error::BadType(format!(
+                "Need Date but get {:?}",
+                self.tag()
+            )))?
+        }
+
+        self.try_into()
+    }
+
+    /// Returns `true` if the value is an `ArrayBuffer`, a `SharedArrayBuffer` is not.
+    pub fn is_array_buffer(&self) -> bool {
+        unsafe { crate::ffi::JS_IsArrayBuffer(self.inner) }
+    }
+
+    /// Returns `true` if the value is a `Map`, objects only inheriting from `Map.prototype`
+    /// are not maps.
+    pub fn is_map(&self) -> bool {
+        unsafe { crate::ffi::JS_IsMap(self.inner) }
+    }
+
+    pub fn to_map(self) -> Result<JsMap<'a>, Error> {
+        if !self.is_map() {
+            Err(Error::BadType(format!("Need Map but get {:?}", self.tag())))?
+        }
+
+        self.try_into()
+    }
+
+    /// Returns `true` if the value is a `Set`, objects only inheriting from `Set.prototype`
+    /// are not sets.
+    pub fn is_set(&self) -> bool {
+        unsafe { crate::ffi::JS_IsSet(self.inner) }
+    }
+
+    pub fn to_set(self) -> Result<JsSet<'a>, Error> {
+        if !self.is_set() {
+            Err(Error::BadType(format!("Need Set but get {:?}", self.tag())))?
+        }
+
+        self.try_into()
+    }
+
+    /// Returns `true` if the value is a `Promise` instance, thenables are not promises.
+    pub fn is_promise(&self) -> bool {
+        is_promise(self.ctx, self.inner)
+    }
+
+    /// Convert the value to JSON like `JSON.stringify(value, replacer, indent)`, values
+    /// without JSON representation like `undefined` or functions are errors.
+    pub fn to_json(
+        &self,
+        indent: Option<u32>,
+        replacer: Option<&JsValue>,
+    ) -> Result<String, Error> {
+        crate::function::json_stringify(self.ctx, self, indent, replacer)
+    }
+
+    pub fn to_promise(self) -> Result<JsPromise<'a>, Error> {
+        if !self.is_promise() {
+            Err(Error::BadType(format!(
+                "Need Promise but get {:?}",
+                self.tag()
+            )))?
+        }
+
+        self.try_into()
+    }
+
     to_fn!(to_int, JsInteger, JsTag::Int, is_int);
     to_fn!(to_number, JsNumber, JsTag::Float64, is_number);
     to_fn!(to_bool, JsBoolean, JsTag::Bool, is_bool);
     to_fn!(to_string, JsString, JsTag::String, is_string);
     to_fn!(to_object, JsObject, JsTag::Object, is_object);
+    to_fn!(to_symbol, JsSymbol, JsTag::Symbol, is_symbol);
+    #[cfg(feature = "bigint")]
+    to_fn!(to_big_int, JsBigInt, JsTag::BigInt, is_big_int);
     // to_fn!(to_module, JsModule, JsTag::Module, is_module);
     to_fn!(
         to_compiled_function,
@@ -712,9 +791,13 @@ impl_from!(JsFunction for JsValue);
 impl_from!(JsCompiledFunction for JsValue);
 // impl_from!(JsModule for JsValue);
 impl_from!(JsArray for JsValue);
+impl_from!(JsSymbol for JsValue);
+#[cfg(feature = "bigint")]
+impl_from!(JsBigInt for JsValue);
 
 struct_type!(JsArray);
 impl<'a> JsArray<'a> {
+    /// Wrap `value`, panics if it is not an array, see [`JsArray::try_new`].
     pub fn new(ctx: &'a crate::Context, value: JSValue) -> Self {
         let is_array = unsafe { crate::ffi::JS_IsArray(ctx.inner, value) == 1 };
 
@@ -724,11 +807,135 @@ impl<'a> JsArray<'a> {
             panic!("Value is not js array");
         }
     }
+
+    /// Wrap `value`, which is freed if it is not an array.
+    pub fn try_new(ctx: &'a crate::Context, value: JSValue) -> Result<Self, Error> {
+        JsValue::new(ctx, value).try_into()
+    }
+
+    pub fn new_empty(ctx: &'a crate::Context) -> Result<Self, Error> {
+        crate::function::new_array(ctx)
+    }
+
+    /// Create an array with the items of `iter`.
+    pub fn from_iter<I>(ctx: &'a crate::Context, iter: I) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: IntoJs<'a>,
+    {
+        let arr = Self::new_empty(ctx)?;
+        for (idx, item) in iter.into_iter().enumerate() {
+            arr.set(idx as u32, item)?;
+        }
+
+        Ok(arr)
+    }
+
+    pub fn len(&self) -> Result<u32, Error> {
+        let len = unsafe { JS_GetPropertyStr(self.ctx.inner, self.inner, c"length".as_ptr()) };
+        let len = JsValue::new(self.ctx, len);
+        assert_exception(self.ctx, &len, "Could not get array length")?;
+
+        // Proxy 的 length 可以是任意值
+        u32::from_js(self.ctx, len)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Get the item at `idx`, `undefined` if it is out of bounds.
+    pub fn get(&self, idx: u32) -> Result<JsValue<'a>, Error> {
+        let item = unsafe { JS_GetPropertyUint32(self.ctx.inner, self.inner, idx) };
+        let item = JsValue::new(self.ctx, item);
+        assert_exception(self.ctx, &item, "Could not get array item")?;
+
+        Ok(item)
+    }
+
+    /// Set the item at `idx`, the array grows if `idx` is out of bounds.
+    pub fn set<V: IntoJs<'a>>(&self, idx: u32, value: V) -> Result<(), Error> {
+        let value = value.into_js(self.ctx)?;
+        // NOTE: JS_SetPropertyUint32 takes ownership of the value.
+        let rst = unsafe { JS_SetPropertyUint32(self.ctx.inner, self.inner, idx, value.forget()) };
+        if rst < 0 {
+            Err(get_last_exception(self.ctx)
+                .unwrap_or_else(|| Error::PropertyError("Could not set array item".into())))?
+        }
+
+        Ok(())
+    }
+
+    /// Append `value` to the end of the array.
+    pub fn push<V: IntoJs<'a>>(&self, value: V) -> Result<(), Error> {
+        self.set(self.len()?, value)
+    }
+
+    /// Iterate over the items, the length is read when the iteration starts.
+    pub fn iter(&self) -> JsArrayIter<'_, 'a> {
+        JsArrayIter {
+            arr: self,
+            idx: 0,
+            len: None,
+        }
+    }
+
+    /// Convert the items into Rust values.
+    pub fn to_vec<T: FromJs<'a>>(&self) -> Result<Vec<T>, Error> {
+        self.iter()
+            .map(|item| T::from_js(self.ctx, item?))
+            .collect()
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+impl<'a> std::fmt::Debug for JsArray<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsArray").field(&self.len().ok()).finish()
+    }
 }
 impl_drop!(JsArray);
 impl_clone!(JsArray);
 impl_try_from!(JsValue for JsArray if v => v.is_array());
 
+/// Iterator returned by [`JsArray::iter`].
+pub struct JsArrayIter<'r, 'a> {
+    arr: &'r JsArray<'a>,
+    idx: u32,
+    len: Option<u32>,
+}
+
+impl<'a> Iterator for JsArrayIter<'_, 'a> {
+    type Item = Result<JsValue<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = match self.len {
+            Some(len) => len,
+            None => match self.arr.len() {
+                Ok(len) => *self.len.insert(len),
+                Err(err) => {
+                    // 长度读取失败时只报告一次错误
+                    self.len = Some(0);
+                    return Some(Err(err));
+                }
+            },
+        };
+        if self.idx >= len {
+            return None;
+        }
+
+        let item = self.arr.get(self.idx);
+        self.idx += 1;
+        Some(item)
+    }
+}
+
 struct_type!(JsObject);
 impl_type_common_fn!(JsObject, Option<JSValue>, crate::ffi::JS_NewObjectWithProto);
 impl<'a> std::fmt::Debug for JsObject<'a> {
@@ -743,78 +950,389 @@ impl_drop!(JsObject);
 impl_clone!(JsObject);
 impl_try_from!(JsValue for JsObject if v => v.is_object());
 impl<'a> JsObject<'a> {
-    pub fn property(&self, name: &str) -> Option<JsValue<'a>> {
-        // TODO: prevent allocation
-        let cname = if let Ok(val) = crate::common::make_cstring(name) {
-            val
-        } else {
-            return None;
-        };
+    pub fn property<K: IntoPropertyKey<'a>>(&self, key: K) -> Option<JsValue<'a>> {
+        self.as_value().get_property(key)
+    }
 
-        let value = {
-            let val = unsafe {
-                crate::ffi::JS_GetPropertyStr(self.ctx.inner, self.inner, cname.as_ptr())
-            };
-            JsValue::new(self.ctx, val)
-        };
-        let tag = value.tag();
+    /// Returns `true` if the object is a `Promise` instance, thenables are not promises.
+    pub fn is_promise(&self) -> bool {
+        is_promise(self.ctx, self.inner)
+    }
 
-        if tag.is_exception() {
-            None
-        } else if tag.is_undefined() {
-            None
-        } else {
-            Some(value)
+    /// Own enumerable string keys, like `Object.keys()`.
+    pub fn keys(&self) -> Result<Vec<JsAtom<'a>>, Error> {
+        self.keys_with(PropertyFilter::default())
+    }
+
+    /// Own keys selected by `filter`, in property order.
+    pub fn keys_with(&self, filter: PropertyFilter) -> Result<Vec<JsAtom<'a>>, Error> {
+        crate::function::own_property_atoms(self.ctx, self.as_value(), filter.flags())
+    }
+
+    /// Own enumerable string keyed properties, like `Object.entries()`.
+    pub fn entries(&self) -> Result<Vec<(JsAtom<'a>, JsValue<'a>)>, Error> {
+        self.entries_with(PropertyFilter::default())
+    }
+
+    /// Own properties selected by `filter`, getters are called to get the values.
+    pub fn entries_with(
+        &self,
+        filter: PropertyFilter,
+    ) -> Result<Vec<(JsAtom<'a>, JsValue<'a>)>, Error> {
+        self.keys_with(filter)?
+            .into_iter()
+            .map(|key| {
+                let prop = PropertyKey::Atom(key.clone());
+                let val = crate::function::get_property(self.ctx, self.as_value(), &prop)?;
+                Ok((key, val))
+            })
+            .collect()
+    }
+
+    /// Check if the object or its prototype chain has the property `key`.
+    pub fn has_property<K: IntoPropertyKey<'a>>(&self, key: K) -> Result<bool, Error> {
+        let key = key.into_key(self.ctx)?.to_atom(self.ctx)?;
+        crate::function::has_property(self.ctx, self.as_value(), &key)
+    }
+
+    /// Delete the own property `key`, `false` if it is not configurable.
+    pub fn delete_property<K: IntoPropertyKey<'a>>(&self, key: K) -> Result<bool, Error> {
+        let key = key.into_key(self.ctx)?.to_atom(self.ctx)?;
+        crate::function::delete_property(self.ctx, self.as_value(), &key)
+    }
+
+    pub fn get_own_property_descriptor<K: IntoPropertyKey<'a>>(
+        &self,
+        key: K,
+    ) -> Result<Option<PropertyDescriptor<'a>>, Error> {
+        let key = key.into_key(self.ctx)?.to_atom(self.ctx)?;
+        crate::function::get_own_property(self.ctx, self.as_value(), &key)
+    }
+
+    /// Define an accessor property `name` calling the Rust `getter` and `setter`, which get
+    /// `this` as second argument.
+    pub fn define_accessor<G, S>(
+        &self,
+        name: &str,
+        getter: G,
+        setter: S,
+        flags: i32,
+    ) -> Result<(), Error>
+    where
+        G: for<'c> Fn(&'c Context<'c>, JsValue<'c>) -> Result<JsValue<'c>, Error> + 'static,
+        S: for<'c> Fn(&'c Context<'c>, JsValue<'c>, JsValue<'c>) -> Result<(), Error> + 'static,
+    {
+        let setter = self.ctx.new_function(name, move |ctx, this, args| {
+            setter(ctx, this, args.get(0)?)?;
+            Ok(JsValue::new(ctx, JS_UNDEFINED))
+        })?;
+        self.define_getset(name, getter, Some(setter), flags)
+    }
+
+    /// Define a read-only accessor property `name` calling the Rust `getter`.
+    pub fn define_getter<G>(&self, name: &str, getter: G, flags: i32) -> Result<(), Error>
+    where
+        G: for<'c> Fn(&'c Context<'c>, JsValue<'c>) -> Result<JsValue<'c>, Error> + 'static,
+    {
+        self.define_getset(name, getter, None, flags)
+    }
+
+    fn define_getset<G>(
+        &self,
+        name: &str,
+        getter: G,
+        setter: Option<JsValue>,
+        flags: i32,
+    ) -> Result<(), Error>
+    where
+        G: for<'c> Fn(&'c Context<'c>, JsValue<'c>) -> Result<JsValue<'c>, Error> + 'static,
+    {
+        let getter = self
+            .ctx
+            .new_function(name, move |ctx, this, _args| getter(ctx, this))?;
+        let atom = self.ctx.new_atom(name)?;
+        crate::function::define_property_getset(
+            self.ctx,
+            self.as_value(),
+            atom,
+            Some(getter),
+            setter,
+            flags,
+        )
+    }
+
+    fn as_value(&self) -> &JsValue<'a> {
+        // Wrappers share the layout of `JsValue`, see `impl_from!`.
+        unsafe { &*(self as *const Self as *const JsValue<'a>) }
+    }
+
+    pub fn set_property<K: IntoPropertyKey<'a>>(
+        &self,
+        key: K,
+        value: JsValue<'a>,
+    ) -> Result<(), crate::common::Error> {
+        let key = key.into_key(self.ctx)?;
+        crate::function::set_property(self.ctx, self.as_value(), &key, value)
+    }
+}
+
+/// Selects the own properties returned by [`JsObject::keys_with`] and
+/// [`JsObject::entries_with`], the default selects enumerable string keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertyFilter {
+    pub enumerable_only: bool,
+    pub strings: bool,
+    pub symbols: bool,
+}
+
+impl Default for PropertyFilter {
+    fn default() -> Self {
+        Self {
+            enumerable_only: true,
+            strings: true,
+            symbols: false,
         }
     }
+}
 
-    /// Determine if the object is a promise by checking the presence of
-    /// a 'then' and a 'catch' property.
-    pub fn is_promise(&self) -> bool {
-        if let Some(p) = self.property("then") {
-            if p.is_function() {
-                return true;
-            }
+impl PropertyFilter {
+    /// All own keys, like `Reflect.ownKeys()`.
+    pub fn all() -> Self {
+        Self {
+            enumerable_only: false,
+            strings: true,
+            symbols: true,
         }
-        if let Some(p) = self.property("catch") {
-            if p.is_function() {
-                return false;
-            }
+    }
+
+    fn flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.strings {
+            flags |= JS_GPN_STRING_MASK;
+        }
+        if self.symbols {
+            flags |= JS_GPN_SYMBOL_MASK;
         }
-        false
+        if self.enumerable_only {
+            flags |= JS_GPN_ENUM_ONLY;
+        }
+
+        flags
     }
+}
 
-    pub fn set_property(&self, name: &str, value: JsValue<'a>) -> Result<(), crate::common::Error> {
-        let cname = crate::common::make_cstring(name)?;
-        unsafe {
-            // NOTE: SetPropertyStr takes ownership of the value.
-            // We do not, however, call JsValue::forget immediately, so
-            // the inner JSValue is still managed.
-            // `mem::forget` is called below only if SetProperty succeeds.
-            // This prevents leaks when an error occurs.
-            let ret = crate::ffi::JS_SetPropertyStr(
-                self.ctx.inner,
-                self.inner,
-                cname.as_ptr(),
-                value.inner,
-            );
-
-            if ret < 0 {
-                Err(crate::common::Error::PropertyError(
-                    "Could not set property".into(),
-                ))
-            } else {
-                // Now we can call forget to prevent calling the destructor.
-                std::mem::forget(value);
-                Ok(())
-            }
+/// An own property descriptor, `value` is `None` for accessor properties, which have no
+/// `writable` attribute.
+#[derive(Debug, Clone)]
+pub struct PropertyDescriptor<'a> {
+    pub value: Option<JsValue<'a>>,
+    pub getter: Option<JsValue<'a>>,
+    pub setter: Option<JsValue<'a>>,
+    pub writable: bool,
+    pub enumerable: bool,
+    pub configurable: bool,
+}
+
+impl PropertyDescriptor<'_> {
+    pub fn is_accessor(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+struct_type!(JsSymbol);
+impl<'a> JsSymbol<'a> {
+    /// Create a new unique symbol, like `Symbol(description)`.
+    pub fn new(ctx: &'a crate::Context, description: Option<&str>) -> Result<Self, Error> {
+        let ctor = symbol_constructor(ctx)?;
+        let description = description.map(|val| JsString::new(ctx, val).to_value());
+        let args = description.iter().collect::<Vec<_>>();
+
+        call_js_function(ctx, &ctor, None, &args)?.to_symbol()
+    }
+
+    /// Get the symbol of `key` in the global symbol registry, like `Symbol.for(key)`.
+    pub fn for_key(ctx: &'a crate::Context, key: &str) -> Result<Self, Error> {
+        let ctor = symbol_constructor(ctx)?;
+        let func = ctor
+            .get_property("for")
+            .ok_or_else(|| Error::PropertyError("Symbol.for is not found".to_owned()))?;
+        let key = JsString::new(ctx, key).to_value();
+
+        call_js_function(ctx, &func, Some(&ctor), &[&key])?.to_symbol()
+    }
+
+    /// Get the well-known symbol `Symbol[name]`.
+    pub fn well_known(ctx: &'a crate::Context, name: &str) -> Result<Self, Error> {
+        symbol_constructor(ctx)?
+            .get_property(name)
+            .ok_or_else(|| Error::PropertyError(format!("Symbol.{name} is not found")))?
+            .to_symbol()
+    }
+
+    pub fn iterator(ctx: &'a crate::Context) -> Result<Self, Error> {
+        Self::well_known(ctx, "iterator")
+    }
+
+    pub fn async_iterator(ctx: &'a crate::Context) -> Result<Self, Error> {
+        Self::well_known(ctx, "asyncIterator")
+    }
+
+    pub fn to_primitive(ctx: &'a crate::Context) -> Result<Self, Error> {
+        Self::well_known(ctx, "toPrimitive")
+    }
+
+    pub fn to_string_tag(ctx: &'a crate::Context) -> Result<Self, Error> {
+        Self::well_known(ctx, "toStringTag")
+    }
+
+    /// The description of the symbol, `None` if it was created without one.
+    pub fn description(&self) -> Result<Option<String>, Error> {
+        let this = JsValue::from(self.clone());
+        let key = "description".into_key(self.ctx)?;
+        let val = crate::function::get_property(self.ctx, &this, &key)?;
+        if val.is_undefined() {
+            return Ok(None);
         }
+
+        crate::function::js_to_string(self.ctx, &val).map(Some)
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+impl<'a> std::fmt::Debug for JsSymbol<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsSymbol")
+            .field(&self.description().ok().flatten())
+            .finish()
+    }
+}
+impl_eq!(for JsSymbol);
+impl<'s> PartialEq<JsSymbol<'s>> for JsSymbol<'s> {
+    fn eq(&self, other: &JsSymbol) -> bool {
+        // Symbols are equal only if they are the same symbol.
+        unsafe { self.inner.u.ptr == other.inner.u.ptr }
+    }
+}
+
+/// The global `Symbol` function.
+fn symbol_constructor<'a>(ctx: &'a crate::Context) -> Result<JsValue<'a>, Error> {
+    crate::function::get_global_object(ctx)
+        .get_property("Symbol")
+        .ok_or_else(|| Error::PropertyError("Symbol is not found".to_owned()))
+}
+impl_drop!(JsSymbol);
+impl_clone!(JsSymbol);
+impl_try_from!(JsValue for JsSymbol if v => v.is_symbol());
+
+#[cfg(feature = "bigint")]
+struct_type!(JsBigInt);
+#[cfg(feature = "bigint")]
+impl<'a> JsBigInt<'a> {
+    pub fn from_i64(ctx: &'a crate::Context, value: i64) -> Result<Self, Error> {
+        let val = JsValue::new(ctx, unsafe { crate::ffi::JS_NewBigInt64(ctx.inner, value) });
+        assert_exception(ctx, &val, "Could not create BigInt")?;
+
+        val.try_into()
+    }
+
+    pub fn from_u64(ctx: &'a crate::Context, value: u64) -> Result<Self, Error> {
+        let val = JsValue::new(ctx, unsafe {
+            crate::ffi::JS_NewBigUint64(ctx.inner, value)
+        });
+        assert_exception(ctx, &val, "Could not create BigInt")?;
+
+        val.try_into()
+    }
+
+    pub fn from_i128(ctx: &'a crate::Context, value: i128) -> Result<Self, Error> {
+        match i64::try_from(value) {
+            Ok(value) => Self::from_i64(ctx, value),
+            Err(_) => Self::parse(ctx, &value.to_string()),
+        }
+    }
+
+    /// Create a BigInt from a decimal integer string like `-123`.
+    pub fn parse(ctx: &'a crate::Context, value: &str) -> Result<Self, Error> {
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            Err(Error::ValueError(format!(
+                "Invalid BigInt decimal string {value:?}"
+            )))?
+        }
+
+        let ctor = crate::function::get_global_object(ctx)
+            .get_property("BigInt")
+            .ok_or_else(|| Error::PropertyError("BigInt is not found".to_owned()))?;
+        let value = JsString::new(ctx, value).to_value();
+
+        call_js_function(ctx, &ctor, None, &[&value])?.to_big_int()
+    }
+
+    /// The decimal representation of the value.
+    pub fn to_decimal_string(&self) -> Result<String, Error> {
+        crate::function::js_to_string(self.ctx, &JsValue::from(self.clone()))
+    }
+
+    pub fn to_i64(&self) -> Result<i64, Error> {
+        self.to_integer()
+    }
+
+    pub fn to_u64(&self) -> Result<u64, Error> {
+        self.to_integer()
+    }
+
+    pub fn to_i128(&self) -> Result<i128, Error> {
+        self.to_integer()
+    }
+
+    /// Parse the decimal representation, which the engine formats without running scripts.
+    fn to_integer<T: std::str::FromStr>(&self) -> Result<T, Error> {
+        let value = self.to_decimal_string()?;
+        value.parse().map_err(|_| {
+            Error::ValueError(format!(
+                "BigInt {value} is out of range of {}",
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+#[cfg(feature = "bigint")]
+impl<'a> std::fmt::Debug for JsBigInt<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsBigInt")
+            .field(
+                &self
+                    .to_decimal_string()
+                    .unwrap_or_else(|_| "unknown".to_owned()),
+            )
+            .finish()
     }
 }
+#[cfg(feature = "bigint")]
+impl_drop!(JsBigInt);
+#[cfg(feature = "bigint")]
+impl_clone!(JsBigInt);
+#[cfg(feature = "bigint")]
+impl_try_from!(JsValue for JsBigInt if v => v.is_big_int());
 
 struct_type!(JsFunction);
 impl<'a> JsFunction<'a> {
-    pub fn call(&self, args: Vec<JsValue<'a>>) -> Result<JsValue<'a>, crate::common::Error> {
+    pub fn call<A: IntoJsArgs<'a>>(&self, args: A) -> Result<JsValue<'a>, crate::common::Error> {
+        let args = args.into_js_args(self.ctx)?;
         let mut qargs = args.iter().map(|arg| arg.inner).collect::<Vec<_>>();
         let len = qargs.len() as i32;
 
@@ -842,6 +1360,16 @@ impl<'a> JsFunction<'a> {
         Ok(val)
     }
 
+    /// Call the function and convert the result with [`FromJs`].
+    pub fn call_as<R, A>(&self, args: A) -> Result<R, crate::common::Error>
+    where
+        R: FromJs<'a>,
+        A: IntoJsArgs<'a>,
+    {
+        let val = self.call(args)?;
+        R::from_js(self.ctx, val)
+    }
+
     to_value_fn!();
 }
 impl_try_from!(JsValue for JsFunction if v => v.is_function());
@@ -867,18 +1395,902 @@ impl_try_from!(JsValue for JsCompiledFunction if v => v.is_compiled_function());
 impl_drop!(JsCompiledFunction);
 impl_clone!(JsCompiledFunction);
 
-pub struct JsExportEntry<'a> {
-    pub(crate) module: &'a JsModuleDef<'a>,
-    pub(crate) inner: &'a JSExportEntry,
+/// Constructor name of the typed array `val`, `None` for other values.
+fn typed_array_constructor(val: JSValue) -> Option<&'static str> {
+    // 顺序与 JS_GetTypedArrayType 返回的序号一致
+    const NAMES: [&str; 11] = [
+        "Uint8ClampedArray",
+        "Int8Array",
+        "Uint8Array",
+        "Int16Array",
+        "Uint16Array",
+        "Int32Array",
+        "Uint32Array",
+        "BigInt64Array",
+        "BigUint64Array",
+        "Float32Array",
+        "Float64Array",
+    ];
+
+    let ty = unsafe { crate::ffi::JS_GetTypedArrayType(val) };
+    usize::try_from(ty)
+        .ok()
+        .and_then(|ty| NAMES.get(ty))
+        .copied()
 }
 
-impl<'a> JsExportEntry<'a> {
-    pub fn new(module: &'a JsModuleDef<'a>, inner: &'a JSExportEntry) -> Self {
-        Self { module, inner }
-    }
+fn is_promise(ctx: &crate::Context, val: JSValue) -> bool {
+    unsafe { JS_PromiseState(ctx.inner, val) >= 0 }
+}
 
-    pub fn module(&self) -> &JsModuleDef {
-        self.module
+/// State of a [`JsPromise`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromiseState {
+    Pending,
+    Fulfilled,
+    Rejected,
+}
+
+struct_type!(JsPromise);
+impl<'a> JsPromise<'a> {
+    pub fn state(&self) -> PromiseState {
+        match unsafe { JS_PromiseState(self.ctx.inner, self.inner) } {
+            JSPromiseStateEnum_JS_PROMISE_FULFILLED => PromiseState::Fulfilled,
+            JSPromiseStateEnum_JS_PROMISE_REJECTED => PromiseState::Rejected,
+            _ => PromiseState::Pending,
+        }
+    }
+
+    /// The settled result, `None` while pending. A rejection is returned as a
+    /// [`Error::JsException`] carrying the rejection reason, reading it does not mark the
+    /// rejection as handled, see [`JsPromise::mark_handled`].
+    pub fn result(&self) -> Option<Result<JsValue<'a>, Error>> {
+        let state = self.state();
+        if state == PromiseState::Pending {
+            return None;
+        }
+
+        let val = JsValue::new(self.ctx, unsafe {
+            JS_PromiseResult(self.ctx.inner, self.inner)
+        });
+        match state {
+            PromiseState::Rejected => {
+                let exception = new_exception(self.ctx, &val);
+                Some(Err(Error::JsException(Box::new(exception))))
+            }
+            _ => Some(Ok(val)),
+        }
+    }
+
+    /// Stop reporting the rejection of this promise as unhandled (see
+    /// [`Runtime::run_jobs_until_idle`](crate::Runtime::run_jobs_until_idle)). Awaiting the
+    /// promise and [`JsPromise::resolve_sync`] do it when they return the rejection.
+    pub fn mark_handled(&self) {
+        let rt = self.ctx.get_runtime();
+        rt.state().forget_rejection(rt.inner, self.inner);
+    }
+
+    /// [`JsPromise::result`], a rejection passed on to Rust is marked as handled.
+    pub(crate) fn handled_result(&self) -> Option<Result<JsValue<'a>, Error>> {
+        let rst = self.result()?;
+        if rst.is_err() {
+            self.mark_handled();
+        }
+
+        Some(rst)
+    }
+
+    /// Run the pending jobs of the runtime until the promise is settled and return its result.
+    pub fn resolve_sync(&self) -> Result<JsValue<'a>, Error> {
+        loop {
+            if let Some(rst) = self.handled_result() {
+                return rst;
+            }
+
+            if !self.ctx.get_runtime().execute_pending_job()? {
+                Err(Error::ExecuteError(
+                    "Promise is pending but no job is left to run".to_owned(),
+                ))?
+            }
+        }
+    }
+
+    /// Call `on_fulfilled` with the value of the promise, returns the derived promise.
+    pub fn then<F>(&self, on_fulfilled: F) -> Result<JsPromise<'a>, Error>
+    where
+        F: for<'c> FnOnce(&'c crate::Context<'c>, JsValue<'c>) -> Result<JsValue<'c>, Error>
+            + 'static,
+    {
+        let handler = once_handler(self.ctx, "then", on_fulfilled)?;
+        self.invoke("then", &[&handler])
+    }
+
+    /// Call `on_rejected` with the rejection reason, returns the derived promise.
+    pub fn catch<F>(&self, on_rejected: F) -> Result<JsPromise<'a>, Error>
+    where
+        F: for<'c> FnOnce(&'c crate::Context<'c>, JsValue<'c>) -> Result<JsValue<'c>, Error>
+            + 'static,
+    {
+        let handler = once_handler(self.ctx, "catch", on_rejected)?;
+        self.invoke("catch", &[&handler])
+    }
+
+    /// Call `on_finally` once the promise is settled, returns the derived promise which is
+    /// settled like this one unless `on_finally` fails.
+    pub fn finally<F>(&self, on_finally: F) -> Result<JsPromise<'a>, Error>
+    where
+        F: FnOnce(&crate::Context) -> Result<(), Error> + 'static,
+    {
+        let handler = once_handler(self.ctx, "finally", move |ctx, _| {
+            on_finally(ctx)?;
+            Ok(JsValue::new(ctx, JS_UNDEFINED))
+        })?;
+        self.invoke("finally", &[&handler])
+    }
+
+    fn invoke(&self, method: &str, args: &[&JsValue]) -> Result<JsPromise<'a>, Error> {
+        let this = JsValue::from(self.clone());
+        let func = this.get_property(method).ok_or_else(|| {
+            Error::PropertyError(format!("Promise.prototype.{method} is not found"))
+        })?;
+
+        call_js_function(self.ctx, &func, Some(&this), args)?.to_promise()
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+/// A promise reaction calling `f` with its first argument, at most once.
+fn once_handler<'a, F>(
+    ctx: &'a crate::Context,
+    name: &'static str,
+    f: F,
+) -> Result<JsValue<'a>, Error>
+where
+    F: for<'c> FnOnce(&'c crate::Context<'c>, JsValue<'c>) -> Result<JsValue<'c>, Error> + 'static,
+{
+    let f = std::cell::Cell::new(Some(f));
+    crate::function::new_function(ctx, name, move |ctx, _this, args| match f.take() {
+        Some(f) => f(ctx, args.get(0)?),
+        None => Err(Error::GeneralError(format!(
+            "Promise {name} handler is called twice"
+        ))),
+    })
+}
+
+impl<'a> std::fmt::Debug for JsPromise<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsPromise").field(&self.state()).finish()
+    }
+}
+impl_try_from!(JsValue for JsPromise if v => v.is_promise());
+impl_from!(JsPromise for JsValue);
+impl_drop!(JsPromise);
+impl_clone!(JsPromise);
+
+struct_type!(JsArrayBuffer);
+impl<'a> JsArrayBuffer<'a> {
+    /// Create an `ArrayBuffer` over `data` without copying it, `data` is dropped once the
+    /// buffer is detached or collected.
+    pub fn new(ctx: &'a crate::Context, data: Vec<u8>) -> Result<Self, Error> {
+        let val = crate::function::new_array_buffer(ctx, data)?;
+        Ok(Self {
+            ctx,
+            inner: unsafe { val.forget() },
+        })
+    }
+
+    /// Create an `ArrayBuffer` holding a copy of `data`.
+    pub fn copy_from(ctx: &'a crate::Context, data: &[u8]) -> Result<Self, Error> {
+        let val = crate::function::new_array_buffer_copy(ctx, data)?;
+        Ok(Self {
+            ctx,
+            inner: unsafe { val.forget() },
+        })
+    }
+
+    /// The byte length, 0 once detached.
+    pub fn len(&self) -> usize {
+        self.raw_parts().map_or(0, |(_, len)| len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.raw_parts().is_err()
+    }
+
+    /// Copy the bytes of the buffer, fails if it is detached.
+    pub fn to_vec(&self) -> Result<Vec<u8>, Error> {
+        Ok(unsafe { self.as_slice() }?.to_vec())
+    }
+
+    /// Borrow the bytes of the buffer without copying them, fails if it is detached.
+    ///
+    /// # Safety
+    ///
+    /// Scripts and other handles of the same buffer can write to it or detach it, which
+    /// frees the memory. While the slice is alive no script may run, the buffer must not be
+    /// detached and no mutable slice of it may exist.
+    pub unsafe fn as_slice(&self) -> Result<&[u8], Error> {
+        let (ptr, len) = self.raw_parts()?;
+        Ok(std::slice::from_raw_parts(ptr, len))
+    }
+
+    /// Mutably borrow the bytes of the buffer without copying them, fails if it is detached.
+    ///
+    /// # Safety
+    ///
+    /// See [`JsArrayBuffer::as_slice`], in addition no other slice of the buffer may exist
+    /// while this one is alive.
+    pub unsafe fn as_mut_slice(&mut self) -> Result<&mut [u8], Error> {
+        let (ptr, len) = self.raw_parts()?;
+        Ok(std::slice::from_raw_parts_mut(ptr, len))
+    }
+
+    /// Detach the buffer, its memory is released and its views become empty.
+    pub fn detach(&mut self) {
+        crate::function::detach_array_buffer(self.ctx, self.as_value())
+    }
+
+    fn raw_parts(&self) -> Result<(*mut u8, usize), Error> {
+        crate::function::get_array_buffer(self.ctx, self.as_value())
+    }
+
+    fn as_value(&self) -> &JsValue<'a> {
+        // Wrappers share the layout of `JsValue`, see `impl_from!`.
+        unsafe { &*(self as *const Self as *const JsValue<'a>) }
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+impl<'a> std::fmt::Debug for JsArrayBuffer<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsArrayBuffer")
+            .field("len", &self.len())
+            .field("detached", &self.is_detached())
+            .finish()
+    }
+}
+impl_try_from!(JsValue for JsArrayBuffer if v => v.is_array_buffer());
+impl_from!(JsArrayBuffer for JsValue);
+impl_drop!(JsArrayBuffer);
+impl_clone!(JsArrayBuffer);
+
+/// Element type of a [`JsTypedArray`].
+///
+/// # Safety
+///
+/// Every bit pattern of the size of `Self` must be a valid value and `CONSTRUCTOR` must be
+/// the name of the typed array with elements of `Self`.
+pub unsafe trait TypedArrayElement: Copy + 'static {
+    const CONSTRUCTOR: &'static str;
+}
+
+macro_rules! impl_typed_array_element {
+    ($($type:ty => $ctor:literal),*) => {
+        $(
+            unsafe impl TypedArrayElement for $type {
+                const CONSTRUCTOR: &'static str = $ctor;
+            }
+        )*
+    };
+}
+
+impl_typed_array_element!(
+    u8 => "Uint8Array", i8 => "Int8Array", u16 => "Uint16Array", i16 => "Int16Array",
+    u32 => "Uint32Array", i32 => "Int32Array", f32 => "Float32Array", f64 => "Float64Array"
+);
+#[cfg(feature = "bigint")]
+impl_typed_array_element!(u64 => "BigUint64Array", i64 => "BigInt64Array");
+
+/// A typed array like `Uint8Array` or `Float64Array`, with elements of type `T`.
+pub struct JsTypedArray<'a, T: TypedArrayElement> {
+    pub(crate) ctx: &'a crate::Context<'a>,
+    pub(crate) inner: JSValue,
+    marker: std::marker::PhantomData<T>,
+}
+impl<'a, T: TypedArrayElement> JsTypedArray<'a, T> {
+    /// Create a typed array over `data` without copying it, `data` is dropped once the
+    /// buffer of the array is detached or collected.
+    pub fn new(ctx: &'a crate::Context, data: Vec<T>) -> Result<Self, Error> {
+        let buffer = crate::function::new_array_buffer(ctx, data)?;
+        Self::construct(ctx, &buffer)
+    }
+
+    /// Create a typed array over `data` without copying it, see [`JsTypedArray::new`].
+    pub fn from_boxed(ctx: &'a crate::Context, data: Box<[T]>) -> Result<Self, Error> {
+        Self::new(ctx, data.into_vec())
+    }
+
+    /// Create a typed array viewing the whole `buffer`, its byte length must be a multiple of
+    /// the element size.
+    pub fn from_array_buffer(buffer: &JsArrayBuffer<'a>) -> Result<Self, Error> {
+        Self::construct(buffer.ctx, buffer.as_value())
+    }
+
+    fn construct(ctx: &'a crate::Context, buffer: &JsValue) -> Result<Self, Error> {
+        let ctor = crate::function::global_constructor(ctx, T::CONSTRUCTOR)?;
+        let val = crate::function::call_constructor(ctx, &ctor, &[buffer])?;
+        // 全局构造函数可以被脚本替换，检查得到的确实是 T 的数组
+        if !val.is_typed_array::<T>() {
+            Err(Error::BadType(format!(
+                "globalThis.{0} did not construct a {0}",
+                T::CONSTRUCTOR
+            )))?
+        }
+
+        Ok(Self {
+            ctx,
+            inner: unsafe { val.forget() },
+            marker: std::marker::PhantomData,
+        })
+    }
+
+    /// The number of elements, 0 once detached.
+    pub fn len(&self) -> usize {
+        self.raw_parts().map_or(0, |(_, len)| len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.buffer().map_or(true, |buffer| buffer.is_detached())
+    }
+
+    /// The `ArrayBuffer` holding the elements.
+    pub fn buffer(&self) -> Result<JsArrayBuffer<'a>, Error> {
+        let (buffer, ..) = crate::function::get_typed_array_buffer(self.ctx, &self.as_value())?;
+        Ok(JsArrayBuffer {
+            ctx: self.ctx,
+            inner: unsafe { buffer.forget() },
+        })
+    }
+
+    /// Copy the elements, fails if the buffer is detached.
+    pub fn to_vec(&self) -> Result<Vec<T>, Error> {
+        Ok(unsafe { self.as_slice() }?.to_vec())
+    }
+
+    /// Borrow the elements without copying them, fails if the buffer is detached.
+    ///
+    /// # Safety
+    ///
+    /// Scripts and other views of the same buffer can write to it or detach it, which frees
+    /// the memory. While the slice is alive no script may run, the buffer must not be
+    /// detached and no mutable slice of it may exist.
+    pub unsafe fn as_slice(&self) -> Result<&[T], Error> {
+        let (ptr, len) = self.raw_parts()?;
+        Ok(std::slice::from_raw_parts(ptr, len))
+    }
+
+    /// Mutably borrow the elements without copying them, fails if the buffer is detached.
+    ///
+    /// # Safety
+    ///
+    /// See [`JsTypedArray::as_slice`], in addition no other slice of the buffer may exist
+    /// while this one is alive.
+    pub unsafe fn as_mut_slice(&mut self) -> Result<&mut [T], Error> {
+        let (ptr, len) = self.raw_parts()?;
+        Ok(std::slice::from_raw_parts_mut(ptr, len))
+    }
+
+    /// Detach the buffer of the array, its memory is released and the array becomes empty.
+    pub fn detach(&mut self) {
+        if let Ok(mut buffer) = self.buffer() {
+            buffer.detach();
+        }
+    }
+
+    fn raw_parts(&self) -> Result<(*mut T, usize), Error> {
+        let (buffer, offset, len) =
+            crate::function::get_typed_array_buffer(self.ctx, &self.as_value())?;
+        let (ptr, _) = crate::function::get_array_buffer(self.ctx, &buffer)?;
+
+        // 由 Vec<u8> 创建的 buffer 不一定满足其它元素类型的对齐要求
+        let ptr = unsafe { ptr.add(offset) } as *mut T;
+        if !ptr.is_aligned() {
+            Err(Error::ValueError(format!(
+                "{} data is not aligned",
+                T::CONSTRUCTOR
+            )))?
+        }
+
+        Ok((ptr, len / std::mem::size_of::<T>()))
+    }
+
+    fn as_value(&self) -> std::mem::ManuallyDrop<JsValue<'a>> {
+        std::mem::ManuallyDrop::new(JsValue::new(self.ctx, self.inner))
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    pub fn raw_value(&self) -> &JSValue {
+        &self.inner
+    }
+
+    pub fn to_value(self) -> JsValue<'a> {
+        self.into()
+    }
+}
+impl<'a, T: TypedArrayElement> std::fmt::Debug for JsTypedArray<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsTypedArray")
+            .field("type", &T::CONSTRUCTOR)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+impl<'a, T: TypedArrayElement> Drop for JsTypedArray<'a, T> {
+    fn drop(&mut self) {
+        unsafe { JS_FreeValue(self.ctx.inner, self.inner) };
+    }
+}
+impl<'a, T: TypedArrayElement> Clone for JsTypedArray<'a, T> {
+    fn clone(&self) -> Self {
+        unsafe { JS_DupValue(self.ctx.inner, self.inner) };
+        Self {
+            ctx: self.ctx,
+            inner: self.inner,
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+impl<'a, T: TypedArrayElement> From<JsTypedArray<'a, T>> for JsValue<'a> {
+    fn from(value: JsTypedArray<'a, T>) -> Self {
+        let value = std::mem::ManuallyDrop::new(value);
+        JsValue::new(value.ctx, value.inner)
+    }
+}
+impl<'a, T: TypedArrayElement> TryFrom<JsValue<'a>> for JsTypedArray<'a, T> {
+    type Error = Error;
+
+    fn try_from(value: JsValue<'a>) -> Result<Self, Self::Error> {
+        if !value.is_typed_array::<T>() {
+            Err(Error::bad_type::<JsValue, Self>("TryFrom"))?
+        }
+
+        Ok(Self {
+            ctx: value.ctx,
+            inner: unsafe { value.forget() },
+            marker: std::marker::PhantomData,
+        })
+    }
+}
+
+struct_type!(JsDate);
+impl<'a> JsDate<'a> {
+    /// Create a date at `time`, the precision below a millisecond is truncated toward the
+    /// past, like the time values of scripts.
+    pub fn new(ctx: &'a crate::Context, time: SystemTime) -> Result<Self, Error> {
+        let millis = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_millis() as f64,
+            Err(err) => {
+                // 纪元之前向下取整，-1.5ms 应为 -2ms
+                let before = err.duration();
+                let partial = before.subsec_nanos() % 1_000_000 != 0;
+                -((before.as_millis() + partial as u128) as f64)
+            }
+        };
+
+        Self::from_epoch_millis(ctx, millis)
+    }
+
+    /// Create a date at `millis` milliseconds since the Unix epoch, fails for an invalid date
+    /// like `NaN` or a time out of the ±8.64e15 ms range of dates.
+    pub fn from_epoch_millis(ctx: &'a crate::Context, millis: f64) -> Result<Self, Error> {
+        let date = Self::try_from(crate::function::new_date(ctx, millis)?)?;
+        date.epoch_millis()?;
+
+        Ok(date)
+    }
+
+    /// Milliseconds since the Unix epoch, like `date.getTime()`, fails for an invalid date.
+    pub fn epoch_millis(&self) -> Result<f64, Error> {
+        let millis = crate::function::date_time_value(self.ctx, self.as_value())?
+            .ok_or_else(|| Error::BadType("Need Date".to_owned()))?;
+        if millis.is_nan() {
+            Err(Error::ValueError("Invalid Date".to_owned()))?
+        }
+
+        Ok(millis)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.epoch_millis().is_ok()
+    }
+
+    /// Convert the date to a `SystemTime`, fails for an invalid date.
+    pub fn to_system_time(&self) -> Result<SystemTime, Error> {
+        let millis = self.epoch_millis()?;
+        let since = Duration::from_millis(millis.abs() as u64);
+        let time = if millis >= 0.0 {
+            UNIX_EPOCH.checked_add(since)
+        } else {
+            UNIX_EPOCH.checked_sub(since)
+        };
+
+        time.ok_or_else(|| {
+            Error::ValueError(format!("{millis} ms can not be converted to SystemTime"))
+        })
+    }
+
+    fn as_value(&self) -> &JsValue<'a> {
+        // Wrappers share the layout of `JsValue`, see `impl_from!`.
+        unsafe { &*(self as *const Self as *const JsValue<'a>) }
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+impl<'a> std::fmt::Debug for JsDate<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsDate")
+            .field(&self.epoch_millis().ok())
+            .finish()
+    }
+}
+impl_try_from!(JsValue for JsDate if v => v.is_date());
+impl_from!(JsDate for JsValue);
+impl_drop!(JsDate);
+impl_clone!(JsDate);
+
+struct_type!(JsMap);
+impl<'a> JsMap<'a> {
+    /// Create an empty `Map`.
+    pub fn new(ctx: &'a crate::Context) -> Result<Self, Error> {
+        Self::try_from(crate::function::new_map(ctx, false)?)
+    }
+
+    /// Create a `Map` holding the `entries`, later entries replace earlier ones with the same
+    /// key.
+    pub fn from_entries<K, V, I>(ctx: &'a crate::Context, entries: I) -> Result<Self, Error>
+    where
+        K: IntoJs<'a>,
+        V: IntoJs<'a>,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let map = Self::new(ctx)?;
+        for (key, value) in entries {
+            map.set(key, value)?;
+        }
+
+        Ok(map)
+    }
+
+    /// Get the value of `key`, `None` if the key is absent. Keys are compared like
+    /// `SameValueZero`, so objects match by identity.
+    pub fn get<K: IntoJs<'a>>(&self, key: K) -> Result<Option<JsValue<'a>>, Error> {
+        let key = key.into_js(self.ctx)?;
+        let value = self.call(MapMethod::Get(&key))?;
+        // 只有 undefined 才需要区分键不存在与存储了 undefined
+        if value.is_undefined() && !self.has_value(&key)? {
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Set `key` to `value`, replacing the old value.
+    pub fn set<K: IntoJs<'a>, V: IntoJs<'a>>(&self, key: K, value: V) -> Result<(), Error> {
+        let key = key.into_js(self.ctx)?;
+        let value = value.into_js(self.ctx)?;
+        self.call(MapMethod::Set(&key, &value))?;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the map holds `key`, keys are compared like `SameValueZero`.
+    pub fn has<K: IntoJs<'a>>(&self, key: K) -> Result<bool, Error> {
+        self.has_value(&key.into_js(self.ctx)?)
+    }
+
+    /// Remove `key`, returns `false` if it was absent.
+    pub fn delete<K: IntoJs<'a>>(&self, key: K) -> Result<bool, Error> {
+        let key = key.into_js(self.ctx)?;
+        bool::from_js(self.ctx, self.call(MapMethod::Delete(&key))?)
+    }
+
+    /// Remove all the entries.
+    pub fn clear(&self) -> Result<(), Error> {
+        self.call(MapMethod::Clear)?;
+
+        Ok(())
+    }
+
+    /// The number of entries.
+    pub fn size(&self) -> Result<u32, Error> {
+        u32::from_js(self.ctx, self.call(MapMethod::Size)?)
+    }
+
+    /// Returns `true` if the map has no entries.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.size()? == 0)
+    }
+
+    /// Iterate over the entries in insertion order, the iteration starts on the first call
+    /// of `next`.
+    pub fn iter(&self) -> JsMapIter<'_, 'a> {
+        JsMapIter {
+            inner: JsIteratorState::new(self.as_value(), false),
+        }
+    }
+
+    /// Convert the entries into a `HashMap`, later entries win if several keys convert to
+    /// the same Rust key.
+    pub fn to_hash_map<K, V, S>(&self) -> Result<HashMap<K, V, S>, Error>
+    where
+        K: FromJs<'a> + Eq + Hash,
+        V: FromJs<'a>,
+        S: BuildHasher + Default,
+    {
+        self.iter()
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((K::from_js(self.ctx, key)?, V::from_js(self.ctx, value)?))
+            })
+            .collect()
+    }
+
+    fn has_value(&self, key: &JsValue) -> Result<bool, Error> {
+        bool::from_js(self.ctx, self.call(MapMethod::Has(key))?)
+    }
+
+    fn call(&self, method: MapMethod) -> Result<JsValue<'a>, Error> {
+        crate::function::call_map_method(self.ctx, self.as_value(), false, method)
+    }
+
+    fn as_value(&self) -> &JsValue<'a> {
+        // Wrappers share the layout of `JsValue`, see `impl_from!`.
+        unsafe { &*(self as *const Self as *const JsValue<'a>) }
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+impl<'a> std::fmt::Debug for JsMap<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsMap").field(&self.size().ok()).finish()
+    }
+}
+impl_try_from!(JsValue for JsMap if v => v.is_map());
+impl_from!(JsMap for JsValue);
+impl_drop!(JsMap);
+impl_clone!(JsMap);
+
+/// Iterator returned by [`JsMap::iter`], yields `(key, value)` pairs.
+pub struct JsMapIter<'r, 'a> {
+    inner: JsIteratorState<'r, 'a>,
+}
+
+impl<'a> Iterator for JsMapIter<'_, 'a> {
+    type Item = Result<(JsValue<'a>, JsValue<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 迭代器只产生键，值通过内建的 get 读取，两者之间不会运行脚本
+        let entry = self.inner.next()?.and_then(|key| {
+            let source = self.inner.source;
+            let value =
+                crate::function::call_map_method(source.ctx, source, false, MapMethod::Get(&key))?;
+            Ok((key, value))
+        });
+
+        Some(entry)
+    }
+}
+
+struct_type!(JsSet);
+impl<'a> JsSet<'a> {
+    /// Create an empty `Set`.
+    pub fn new(ctx: &'a crate::Context) -> Result<Self, Error> {
+        Self::try_from(crate::function::new_map(ctx, true)?)
+    }
+
+    /// Create a `Set` holding the `values`.
+    pub fn from_values<T, I>(ctx: &'a crate::Context, values: I) -> Result<Self, Error>
+    where
+        T: IntoJs<'a>,
+        I: IntoIterator<Item = T>,
+    {
+        let set = Self::new(ctx)?;
+        for value in values {
+            set.add(value)?;
+        }
+
+        Ok(set)
+    }
+
+    /// Add `value`, nothing changes if it is already in the set.
+    pub fn add<T: IntoJs<'a>>(&self, value: T) -> Result<(), Error> {
+        let value = value.into_js(self.ctx)?;
+        let undefined = JsValue::new(self.ctx, JS_UNDEFINED);
+        self.call(MapMethod::Set(&value, &undefined))?;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the set holds `value`, values are compared like `SameValueZero`.
+    pub fn has<T: IntoJs<'a>>(&self, value: T) -> Result<bool, Error> {
+        let value = value.into_js(self.ctx)?;
+        bool::from_js(self.ctx, self.call(MapMethod::Has(&value))?)
+    }
+
+    /// Remove `value`, returns `false` if it was absent.
+    pub fn delete<T: IntoJs<'a>>(&self, value: T) -> Result<bool, Error> {
+        let value = value.into_js(self.ctx)?;
+        bool::from_js(self.ctx, self.call(MapMethod::Delete(&value))?)
+    }
+
+    /// Remove all the values.
+    pub fn clear(&self) -> Result<(), Error> {
+        self.call(MapMethod::Clear)?;
+
+        Ok(())
+    }
+
+    /// The number of values.
+    pub fn size(&self) -> Result<u32, Error> {
+        u32::from_js(self.ctx, self.call(MapMethod::Size)?)
+    }
+
+    /// Returns `true` if the set has no values.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.size()? == 0)
+    }
+
+    /// Iterate over the values in insertion order, the iteration starts on the first call
+    /// of `next`.
+    pub fn iter(&self) -> JsSetIter<'_, 'a> {
+        JsSetIter {
+            inner: JsIteratorState::new(self.as_value(), true),
+        }
+    }
+
+    /// Convert the values into a `HashSet`, values converting to the same Rust value are
+    /// merged.
+    pub fn to_hash_set<T, S>(&self) -> Result<HashSet<T, S>, Error>
+    where
+        T: FromJs<'a> + Eq + Hash,
+        S: BuildHasher + Default,
+    {
+        self.iter()
+            .map(|value| T::from_js(self.ctx, value?))
+            .collect()
+    }
+
+    fn call(&self, method: MapMethod) -> Result<JsValue<'a>, Error> {
+        crate::function::call_map_method(self.ctx, self.as_value(), true, method)
+    }
+
+    fn as_value(&self) -> &JsValue<'a> {
+        // Wrappers share the layout of `JsValue`, see `impl_from!`.
+        unsafe { &*(self as *const Self as *const JsValue<'a>) }
+    }
+
+    pub fn context(&self) -> &'a crate::Context<'a> {
+        self.ctx
+    }
+
+    raw_value_fn!();
+    tag_fn!();
+    to_value_fn!();
+}
+impl<'a> std::fmt::Debug for JsSet<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("JsSet").field(&self.size().ok()).finish()
+    }
+}
+impl_try_from!(JsValue for JsSet if v => v.is_set());
+impl_from!(JsSet for JsValue);
+impl_drop!(JsSet);
+impl_clone!(JsSet);
+
+/// Iterator returned by [`JsSet::iter`].
+pub struct JsSetIter<'r, 'a> {
+    inner: JsIteratorState<'r, 'a>,
+}
+
+impl<'a> Iterator for JsSetIter<'_, 'a> {
+    type Item = Result<JsValue<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// Steps the builtin iterator of the `Map` or `Set` `source`, the iterator is created on
+/// the first call of `next`.
+struct JsIteratorState<'r, 'a> {
+    source: &'r JsValue<'a>,
+    is_set: bool,
+    iter: Option<JsValue<'a>>,
+    done: bool,
+}
+
+impl<'r, 'a> JsIteratorState<'r, 'a> {
+    fn new(source: &'r JsValue<'a>, is_set: bool) -> Self {
+        Self {
+            source,
+            is_set,
+            iter: None,
+            done: false,
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<JsValue<'a>>, Error> {
+        let ctx = self.source.ctx;
+        let iter = match &self.iter {
+            Some(iter) => iter,
+            None => self.iter.insert(crate::function::call_map_method(
+                ctx,
+                self.source,
+                self.is_set,
+                MapMethod::Iterator,
+            )?),
+        };
+
+        crate::function::map_iterator_next(ctx, iter, self.is_set)
+    }
+
+    fn next(&mut self) -> Option<Result<JsValue<'a>, Error>> {
+        if self.done {
+            return None;
+        }
+
+        match self.step() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                // 出错时只报告一次错误，之后结束迭代
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+pub struct JsExportEntry<'a> {
+    pub(crate) module: &'a JsModuleDef<'a>,
+    pub(crate) inner: &'a JSExportEntry,
+}
+
+impl<'a> JsExportEntry<'a> {
+    pub fn new(module: &'a JsModuleDef<'a>, inner: &'a JSExportEntry) -> Self {
+        Self { module, inner }
+    }
+
+    pub fn module(&self) -> &JsModuleDef {
+        self.module
     }
 
     pub fn raw_value(&self) -> &JSExportEntry {
@@ -900,7 +2312,7 @@ impl<'a> JsExportEntry<'a> {
 mod tests {
     use crate::{
         common::Error,
-        function::{compile, js_eval, js_get_global_object},
+        function::{compile, js_eval, js_get_global_object, js_to_string},
         Context, Runtime,
     };
 
@@ -971,7 +2383,7 @@ mod tests {
 
         let js_fn: JsFunction = js_fn.try_into().unwrap();
         let rst = js_fn
-            .call(vec![JsInteger::new(ctx, 2).into()])
+            .call(vec![JsValue::from(JsInteger::new(ctx, 2))])
             .unwrap()
             .to_int()
             .unwrap()
@@ -986,4 +2398,595 @@ mod tests {
         let rst = js_compiled_fn.eval().unwrap().to_int().unwrap().value();
         assert_eq!(6, rst);
     }
+
+    #[test]
+    fn test_array() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let arr = JsArray::new_empty(ctx).unwrap();
+        assert!(arr.is_empty().unwrap());
+        arr.push(1).unwrap();
+        arr.push("two").unwrap();
+        arr.set(3, 4.5).unwrap();
+        assert_eq!(4, arr.len().unwrap());
+        assert_eq!(1, arr.get(0).unwrap().to_int().unwrap().value());
+        assert!(arr.get(2).unwrap().is_undefined());
+        assert!(arr.get(10).unwrap().is_undefined());
+
+        let items = arr.iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(4, items.len());
+        assert_eq!("two", items[1].clone().to_string().unwrap().value());
+
+        let arr = JsArray::from_iter(ctx, [1, 2, 3]).unwrap();
+        assert_eq!(vec![1, 2, 3], arr.to_vec::<i32>().unwrap());
+        assert!(arr.to_vec::<String>().is_err());
+
+        let val = ctx.eval("[10, 20, 30]", "<test>", flags).unwrap();
+        let arr: JsArray = val.try_into().unwrap();
+        assert_eq!(vec![10, 20, 30], arr.to_vec::<u32>().unwrap());
+
+        // 不是数组时返回错误而不是 panic
+        let obj = ctx.eval("({ length: 1 })", "<test>", flags).unwrap();
+        assert!(JsArray::try_new(ctx, obj.dup_value()).is_err());
+        assert!(JsArray::try_from(obj).is_err());
+
+        let code = "new Proxy([], { get: (t, k) => k === 'length' ? 'x' : t[k] })";
+        let arr = JsArray::try_from(ctx.eval(code, "<test>", flags).unwrap()).unwrap();
+        assert!(matches!(arr.len(), Err(Error::BadType(_))));
+        assert!(arr.iter().next().unwrap().is_err());
+
+        let arr = ctx
+            .eval(
+                "Object.defineProperty([1], 0, { get() { throw new Error('boom') } })",
+                "<test>",
+                flags,
+            )
+            .unwrap();
+        let arr: JsArray = arr.try_into().unwrap();
+        assert!(arr.get(0).is_err());
+        assert!(arr.to_vec::<i32>().is_err());
+    }
+
+    #[test]
+    fn test_object() {
+        use std::{cell::Cell, rc::Rc};
+
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let code = r#"
+            const obj = Object.create({ inherited: 1 });
+            obj.a = 1;
+            obj.b = "two";
+            Object.defineProperty(obj, "hidden", { value: 3, enumerable: false });
+            obj[Symbol("tag")] = 4;
+            obj
+        "#;
+        let obj = ctx
+            .eval(code, "<test>", flags)
+            .unwrap()
+            .to_object()
+            .unwrap();
+
+        let keys = obj.keys().unwrap();
+        let keys = keys.iter().map(|key| key.to_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b"], keys);
+        assert_eq!(4, obj.keys_with(PropertyFilter::all()).unwrap().len());
+        let filter = PropertyFilter {
+            enumerable_only: false,
+            ..Default::default()
+        };
+        assert_eq!(3, obj.keys_with(filter).unwrap().len());
+
+        let entries = obj.entries().unwrap();
+        assert_eq!("b", entries[1].0.to_str());
+        assert_eq!("two", entries[1].1.clone().to_string().unwrap().value());
+
+        assert!(obj.has_property("a").unwrap());
+        assert!(obj.has_property("inherited").unwrap());
+        assert!(!obj.has_property("c").unwrap());
+
+        let desc = obj.get_own_property_descriptor("hidden").unwrap().unwrap();
+        assert!(!desc.is_accessor());
+        assert!(!desc.enumerable && !desc.writable && !desc.configurable);
+        assert_eq!(3, desc.value.unwrap().to_int().unwrap().value());
+        assert!(obj
+            .get_own_property_descriptor("inherited")
+            .unwrap()
+            .is_none());
+
+        assert!(obj.delete_property("a").unwrap());
+        assert!(!obj.has_property("a").unwrap());
+        assert!(!obj.delete_property("hidden").unwrap());
+
+        let count = Rc::new(Cell::new(0));
+        let (get, set) = (count.clone(), count.clone());
+        let prop_flags = (crate::ffi::JS_PROP_CONFIGURABLE | crate::ffi::JS_PROP_ENUMERABLE) as i32;
+        obj.define_accessor(
+            "count",
+            move |ctx, _this| Ok(JsInteger::new(ctx, get.get()).to_value()),
+            move |_ctx, _this, val| {
+                set.set(val.to_int()?.value());
+                Ok(())
+            },
+            prop_flags,
+        )
+        .unwrap();
+        obj.define_getter(
+            "answer",
+            |ctx, _this| Ok(JsInteger::new(ctx, 42).to_value()),
+            0,
+        )
+        .unwrap();
+        ctx.get_global_object()
+            .set_property("obj", obj.clone().into())
+            .unwrap();
+
+        let rst = ctx
+            .eval(
+                "obj.count = 5; obj.answer = 0; obj.count + obj.answer",
+                "<test>",
+                flags,
+            )
+            .unwrap();
+        assert_eq!(5, count.get());
+        assert_eq!(47, rst.to_int().unwrap().value());
+        let desc = obj.get_own_property_descriptor("count").unwrap().unwrap();
+        assert!(desc.is_accessor() && desc.enumerable);
+        assert!(desc.getter.is_some() && desc.setter.is_some());
+        let err = ctx.eval("obj.count = 'x'", "<test>", flags).unwrap_err();
+        assert!(matches!(err, Error::JsException(_)));
+    }
+
+    #[test]
+    fn test_symbol() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let sym = JsSymbol::new(ctx, Some("tag")).unwrap();
+        assert_eq!(Some("tag".to_owned()), sym.description().unwrap());
+        assert_ne!(sym, JsSymbol::new(ctx, Some("tag")).unwrap());
+        assert_eq!(
+            None,
+            JsSymbol::new(ctx, None).unwrap().description().unwrap()
+        );
+
+        let registered = JsSymbol::for_key(ctx, "app.id").unwrap();
+        assert_eq!(registered, JsSymbol::for_key(ctx, "app.id").unwrap());
+        let val = ctx.eval("Symbol.for('app.id')", "<test>", flags).unwrap();
+        assert_eq!(registered, JsSymbol::try_from(val).unwrap());
+
+        let iterator = JsSymbol::iterator(ctx).unwrap();
+        assert_eq!(
+            Some("Symbol.iterator".to_owned()),
+            iterator.description().unwrap()
+        );
+        let val = ctx.eval("Symbol.iterator", "<test>", flags).unwrap();
+        assert_eq!(iterator, val.to_symbol().unwrap());
+        let names = [
+            JsSymbol::async_iterator(ctx).unwrap(),
+            JsSymbol::to_primitive(ctx).unwrap(),
+            JsSymbol::to_string_tag(ctx).unwrap(),
+        ]
+        .map(|sym| sym.description().unwrap().unwrap());
+        assert_eq!(
+            [
+                "Symbol.asyncIterator",
+                "Symbol.toPrimitive",
+                "Symbol.toStringTag"
+            ],
+            names
+        );
+        assert!(JsSymbol::well_known(ctx, "missing").is_err());
+
+        let obj = ctx.new_object().unwrap();
+        let tag = JsString::new(ctx, "Custom").to_value();
+        obj.set_property(JsSymbol::to_string_tag(ctx).unwrap(), tag)
+            .unwrap();
+        ctx.get_global_object().set_property("obj", obj).unwrap();
+        let rst = ctx
+            .eval("Object.prototype.toString.call(obj)", "<test>", flags)
+            .unwrap();
+        assert_eq!("[object Custom]", rst.to_string().unwrap().value());
+
+        let not_symbol = ctx.eval("'iterator'", "<test>", flags).unwrap();
+        assert!(JsSymbol::try_from(not_symbol).is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_big_int() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let id = JsBigInt::from_i64(ctx, 9_007_199_254_740_993).unwrap();
+        assert_eq!(JsTag::BigInt, id.tag());
+        assert_eq!(9_007_199_254_740_993, id.to_i64().unwrap());
+        ctx.get_global_object()
+            .set_property("id", id.to_value())
+            .unwrap();
+        let next: JsBigInt = ctx
+            .eval("id + 1n", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!("9007199254740994", next.to_decimal_string().unwrap());
+
+        let max = JsBigInt::from_u64(ctx, u64::MAX).unwrap();
+        assert_eq!(u64::MAX, max.to_u64().unwrap());
+        assert!(max.to_i64().is_err());
+        let neg = JsBigInt::from_i64(ctx, -1).unwrap();
+        assert!(neg.to_u64().is_err());
+
+        let big = JsBigInt::from_i128(ctx, i128::MIN).unwrap();
+        assert_eq!(i128::MIN, big.to_i128().unwrap());
+        assert_eq!(i128::MIN.to_string(), big.to_decimal_string().unwrap());
+        let parsed = JsBigInt::parse(ctx, "-123456789012345678901234567890").unwrap();
+        assert_eq!(-123456789012345678901234567890, parsed.to_i128().unwrap());
+        assert!(JsBigInt::parse(ctx, "0x10").is_err());
+        assert!(JsBigInt::parse(ctx, "-").is_err());
+        assert!(JsBigInt::parse(ctx, "1.5").is_err());
+
+        let val = ctx.eval("2n ** 64n", "<test>", flags).unwrap();
+        assert!(val.is_big_int());
+        assert!(val.to_big_int().unwrap().to_u64().is_err());
+        let not_big_int = ctx.eval("1", "<test>", flags).unwrap();
+        assert!(JsBigInt::try_from(not_big_int).is_err());
+
+        // 范围边界
+        let big_int = |code: &str| {
+            ctx.eval(code, "<test>", flags)
+                .unwrap()
+                .to_big_int()
+                .unwrap()
+        };
+        assert_eq!(i64::MIN, big_int("-(2n ** 63n)").to_i64().unwrap());
+        assert!(big_int("2n ** 63n").to_i64().is_err());
+        assert_eq!(1 << 63, big_int("2n ** 63n").to_u64().unwrap());
+        assert_eq!(i128::MAX, big_int("2n ** 127n - 1n").to_i128().unwrap());
+        assert_eq!(1 << 64, big_int("2n ** 64n").to_i128().unwrap());
+        assert!(big_int("2n ** 127n").to_i128().is_err());
+        assert!(big_int("-(2n ** 127n) - 1n").to_i128().is_err());
+
+        let equal = |a: &str, b: &str| {
+            let (a, b) = (big_int(a), big_int(b));
+            unsafe { crate::ffi::JS_Equal(ctx.inner, a.raw_value(), b.raw_value()) }
+        };
+        assert!(equal("2n ** 64n", "2n ** 65n / 2n"));
+        assert!(!equal("2n ** 64n", "2n ** 64n + 1n"));
+    }
+
+    #[test]
+    fn test_big_int_tag() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        // 未启用 bigint 特性时也能识别 BigInt 的标签
+        let val = ctx.eval("1n", "<test>", flags).unwrap();
+        assert_eq!(JsTag::BigInt, val.tag());
+        assert!(val.is_big_int());
+    }
+
+    #[test]
+    fn test_array_buffer() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let eval = |code: &str| js_eval(ctx, code, "<input>", flags).unwrap();
+        let global = ctx.get_global_object();
+
+        // 不复制数据，脚本与 Rust 共享同一块内存
+        let data = vec![1_u8, 2, 3];
+        let ptr = data.as_ptr();
+        let mut buf = JsArrayBuffer::new(ctx, data).unwrap();
+        assert_eq!(ptr, unsafe { buf.as_slice() }.unwrap().as_ptr());
+        assert_eq!(3, buf.len());
+        global.set_property("buf", buf.clone().to_value()).unwrap();
+        eval("new Uint8Array(buf)[0] = 9");
+        assert_eq!(vec![9, 2, 3], buf.to_vec().unwrap());
+        unsafe { buf.as_mut_slice() }.unwrap()[2] = 7;
+        assert_eq!(7, eval("new Uint8Array(buf)[2]").to_int().unwrap().value());
+        assert!(eval("buf").is_array_buffer());
+        assert!(!eval("Object.create(ArrayBuffer.prototype)").is_array_buffer());
+        assert!(!eval("new SharedArrayBuffer(1)").is_array_buffer());
+
+        buf.detach();
+        assert!(buf.is_detached());
+        assert_eq!(0, buf.len());
+        assert!(buf.to_vec().is_err());
+        assert_eq!(0, eval("buf.byteLength").to_int().unwrap().value());
+
+        // 通过另一个句柄分离后，原句柄也看不到数据
+        let buf = JsArrayBuffer::new(ctx, vec![1, 2, 3, 4]).unwrap();
+        let arr = JsTypedArray::<u16>::from_array_buffer(&buf).unwrap();
+        buf.clone().detach();
+        assert!(buf.is_detached());
+        assert!(buf.to_vec().is_err());
+        assert!(unsafe { buf.as_slice() }.is_err());
+        assert!(arr.is_detached());
+        assert!(arr.to_vec().is_err());
+        assert_eq!(0, arr.len());
+
+        let mut arr = JsTypedArray::new(ctx, vec![1.5_f64, 2.5]).unwrap();
+        global.set_property("arr", arr.clone().to_value()).unwrap();
+        assert!(eval("arr instanceof Float64Array")
+            .to_bool()
+            .unwrap()
+            .value());
+        assert_eq!(4.0, eval("arr[0] + arr[1]").to_number().unwrap().value());
+        unsafe { arr.as_mut_slice() }.unwrap()[1] = 0.5;
+        assert_eq!(0.5, eval("arr[1]").to_number().unwrap().value());
+
+        let arr: JsTypedArray<i32> = eval("new Int32Array([1, -2, 3]).subarray(1)")
+            .try_into()
+            .unwrap();
+        assert_eq!(vec![-2, 3], arr.to_vec().unwrap());
+        assert_eq!(12, arr.buffer().unwrap().len());
+        assert!(JsTypedArray::<u8>::try_from(arr.to_value()).is_err());
+        assert!(eval("new Uint8Array(1)").is_typed_array::<u8>());
+        assert!(!eval("new Uint8ClampedArray(1)").is_typed_array::<u8>());
+        assert!(!eval("Object.create(Uint8Array.prototype)").is_typed_array::<u8>());
+        assert!(eval("Uint8Array = null; new Int8Array(1)").is_typed_array::<i8>());
+        eval("const RealFloat64Array = Float64Array; Float64Array = Uint8Array");
+        assert!(JsTypedArray::new(ctx, vec![1.5_f64]).is_err());
+        eval("Float64Array = RealFloat64Array");
+
+        let boxed: Box<[u16]> = vec![1, 2].into_boxed_slice();
+        let mut arr = JsTypedArray::from_boxed(ctx, boxed).unwrap();
+        assert_eq!(2, arr.len());
+        arr.detach();
+        assert!(arr.is_detached());
+        assert!(arr.is_empty());
+
+        let buf = JsArrayBuffer::copy_from(ctx, &[1, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+        let arr = JsTypedArray::<u32>::from_array_buffer(&buf).unwrap();
+        let expected = [
+            u32::from_le_bytes([1, 0, 0, 0]),
+            u32::from_le_bytes([2, 0, 0, 0]),
+        ];
+        assert_eq!(&expected, unsafe { arr.as_slice() }.unwrap());
+        let buf = JsArrayBuffer::copy_from(ctx, &[0; 3]).unwrap();
+        assert!(JsTypedArray::<f64>::from_array_buffer(&buf).is_err());
+    }
+
+    #[test]
+    fn test_date() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let eval = |code: &str| js_eval(ctx, code, "<input>", flags).unwrap();
+
+        let date = eval("new Date(0)").to_date().unwrap();
+        assert_eq!(0.0, date.epoch_millis().unwrap());
+        assert_eq!(UNIX_EPOCH, date.to_system_time().unwrap());
+        assert!(!eval("Date.now()").is_date());
+        assert!(eval("({})").to_date().is_err());
+        // 按类型判断，不受原型链和全局变量的影响
+        assert!(!eval("Object.create(Date.prototype)").is_date());
+        let date = eval("const RealDate = Date; Date = function () {}; new RealDate(5)");
+        assert!(date.is_date());
+        // 读取和创建日期不经过脚本可以替换的 getTime 和全局 Date
+        eval("Date.prototype.getTime = () => 42");
+        assert_eq!(5.0, date.to_date().unwrap().epoch_millis().unwrap());
+        assert!(eval("({ getTime: () => 1 })").to_date().is_err());
+        let date = JsDate::from_epoch_millis(ctx, 7.0).unwrap();
+        assert_eq!(7.0, date.epoch_millis().unwrap());
+        eval("Date = RealDate");
+
+        // 毫秒以下的精度被截断
+        let time = UNIX_EPOCH + Duration::from_micros(1_500_000_999);
+        let date = JsDate::new(ctx, time).unwrap();
+        assert_eq!(1_500_000.0, date.epoch_millis().unwrap());
+        ctx.get_global_object()
+            .set_property("date", date.to_value())
+            .unwrap();
+        let iso = eval("date.toISOString()").to_string().unwrap();
+        assert_eq!("1970-01-01T00:25:00.000Z", iso.value());
+
+        let before_epoch = UNIX_EPOCH - Duration::from_secs(86_400);
+        let val = before_epoch.into_js(ctx).unwrap();
+        assert!(val.is_date());
+        assert_eq!(before_epoch, SystemTime::from_js(ctx, val).unwrap());
+        let date = JsDate::new(ctx, UNIX_EPOCH - Duration::from_micros(1_500)).unwrap();
+        assert_eq!(-2.0, date.epoch_millis().unwrap());
+        let date = JsDate::new(ctx, UNIX_EPOCH - Duration::from_micros(2_000)).unwrap();
+        assert_eq!(-2.0, date.epoch_millis().unwrap());
+        let times: Vec<SystemTime> = FromJs::from_js(ctx, eval("[new Date(1000)]")).unwrap();
+        assert_eq!(vec![UNIX_EPOCH + Duration::from_secs(1)], times);
+
+        let invalid = eval("new Date(NaN)").to_date().unwrap();
+        assert!(!invalid.is_valid());
+        assert!(matches!(
+            invalid.to_system_time(),
+            Err(Error::ValueError(_))
+        ));
+        assert!(SystemTime::from_js(ctx, invalid.to_value()).is_err());
+        assert!(JsDate::from_epoch_millis(ctx, 9e15).is_err());
+        assert!(JsDate::from_epoch_millis(ctx, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_map_set() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let eval = |code: &str| js_eval(ctx, code, "<input>", flags).unwrap();
+
+        let map = eval("new Map([[1, 'a'], ['k', 'b']])").to_map().unwrap();
+        assert_eq!(2, map.size().unwrap());
+        assert!(map.has(1).unwrap());
+        assert!(!map.has("1").unwrap());
+        assert_eq!(
+            "b",
+            map.get("k").unwrap().unwrap().to_string().unwrap().value()
+        );
+        assert!(map.get("x").unwrap().is_none());
+        assert!(!eval("({})").is_map());
+        assert!(eval("new Set()").to_map().is_err());
+        assert!(!eval("Object.create(Map.prototype)").is_map());
+        assert!(!eval("Object.create(Set.prototype)").is_set());
+
+        // 值为 undefined 的键也存在
+        map.set("u", JsValue::new(ctx, JS_UNDEFINED)).unwrap();
+        assert!(map.get("u").unwrap().unwrap().is_undefined());
+        assert!(map.delete("u").unwrap());
+        assert!(!map.delete("u").unwrap());
+
+        // 按插入顺序迭代
+        map.set(2.5, "c").unwrap();
+        let keys = map
+            .iter()
+            .map(|entry| js_to_string(ctx, &entry.unwrap().0).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["1", "k", "2.5"], keys);
+
+        let map = eval("new Map([[1, 'a'], [2, 'b']])").to_map().unwrap();
+        let rst: HashMap<i32, String> = map.to_hash_map().unwrap();
+        assert_eq!(HashMap::from([(1, "a".into()), (2, "b".into())]), rst);
+        let rst: HashMap<i32, String> = FromJs::from_js(ctx, map.clone().to_value()).unwrap();
+        assert_eq!(2, rst.len());
+        let rst: HashMap<String, i32> = FromJs::from_js(ctx, eval("({ a: 1 })")).unwrap();
+        assert_eq!(HashMap::from([("a".into(), 1)]), rst);
+        let rst: Result<HashMap<String, String>, _> = map.to_hash_map();
+        assert!(rst.is_err());
+
+        let map = JsMap::from_entries(ctx, [("x", 1), ("y", 2)]).unwrap();
+        ctx.get_global_object()
+            .set_property("m", map.clone().to_value())
+            .unwrap();
+        assert_eq!(3, eval("m.get('x') + m.get('y')").to_int().unwrap().value());
+        map.clear().unwrap();
+        assert!(map.is_empty().unwrap());
+
+        let set = eval("new Set(['a', 'b', 'a'])").to_set().unwrap();
+        assert_eq!(2, set.size().unwrap());
+        assert!(set.has("a").unwrap());
+        set.add("c").unwrap();
+        assert!(set.delete("b").unwrap());
+        let values = set
+            .iter()
+            .map(|value| js_to_string(ctx, &value.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["a", "c"], values);
+        assert!(eval("new Map()").to_set().is_err());
+
+        let values = HashSet::from([1, 2, 3]);
+        let val = values.clone().into_js(ctx).unwrap();
+        assert!(val.is_set());
+        assert_eq!(values, HashSet::<i32>::from_js(ctx, val).unwrap());
+        assert!(HashSet::<i32>::from_js(ctx, eval("[1]")).is_err());
+
+        // 脚本替换的方法和全局构造函数不影响内建操作
+        let map = eval(
+            "const m = new Map([[1, 'a']]); \
+             m.get = m.has = m.set = m.entries = () => { throw new Error('patched') }; \
+             Map.prototype.get = Map.prototype.set = Set.prototype.add = undefined; \
+             Object.defineProperty(Map.prototype, 'size', { get() { return 9 } }); \
+             Map = Set = null; m",
+        )
+        .to_map()
+        .unwrap();
+        assert_eq!(
+            "a",
+            map.get(1).unwrap().unwrap().to_string().unwrap().value()
+        );
+        map.set(2, "b").unwrap();
+        assert!(map.has(2).unwrap());
+        assert_eq!(2, map.size().unwrap());
+        assert_eq!(2, map.iter().count());
+        let set = JsSet::from_values(ctx, [1, 2]).unwrap();
+        assert_eq!(
+            vec![1, 2],
+            set.iter()
+                .map(|v| i32::from_js(ctx, v.unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        );
+        ctx.get_global_object()
+            .set_property("s", set.clone().to_value())
+            .unwrap();
+        eval(
+            "const done = () => ({ done: true }); \
+             Object.getPrototypeOf(m.keys()).next = Object.getPrototypeOf(s.values()).next = done; \
+             Object.getPrototypeOf(m).keys = Object.getPrototypeOf(s).values = done",
+        );
+        let entries = map
+            .iter()
+            .map(|entry| {
+                let (key, value) = entry.unwrap();
+                (
+                    i32::from_js(ctx, key).unwrap(),
+                    String::from_js(ctx, value).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, "a".to_owned()), (2, "b".to_owned())], entries);
+        assert_eq!(2, set.iter().count());
+        assert!(JsMap::new(ctx).unwrap().is_empty().unwrap());
+    }
+
+    #[test]
+    fn test_promise() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let eval = |code: &str| js_eval(ctx, code, "<input>", flags).unwrap();
+
+        assert!(eval("Promise.resolve(1)").is_promise());
+        assert!(eval("(async () => {})()").is_promise());
+        assert!(eval("new (class extends Promise {})(() => {})").is_promise());
+        assert!(!eval("({ then() {}, catch() {} })").is_promise());
+        assert!(!eval("({ then: 1 })").is_promise());
+        assert!(!eval("1").is_promise());
+        assert!(eval("Promise.resolve(1)").to_object().unwrap().is_promise());
+        assert!(matches!(eval("({})").to_promise(), Err(Error::BadType(_))));
+
+        let promise = eval("Promise.resolve(20)").to_promise().unwrap();
+        let derived = promise
+            .then(|ctx, val| {
+                let n = val.to_int()?.value();
+                Ok(JsInteger::new(ctx, n + 1).into())
+            })
+            .unwrap()
+            .then(|_ctx, _val| Err(Error::ValueError("failed".to_owned())))
+            .unwrap()
+            .catch(|ctx, reason| {
+                let msg = crate::function::js_to_string(ctx, &reason)?;
+                Ok(JsString::new(ctx, &msg).into())
+            })
+            .unwrap();
+        let rst = derived.resolve_sync().unwrap();
+        assert_eq!(
+            "Error: Value error: failed",
+            rst.to_string().unwrap().value()
+        );
+
+        let called = std::rc::Rc::new(std::cell::Cell::new(false));
+        let flag = called.clone();
+        let derived = eval("Promise.reject(new Error('bad'))")
+            .to_promise()
+            .unwrap()
+            .finally(move |_ctx| {
+                flag.set(true);
+                Ok(())
+            })
+            .unwrap();
+        match derived.resolve_sync() {
+            Err(Error::JsException(ex)) => assert_eq!("bad", ex.message()),
+            rst => panic!("unexpected result {rst:?}"),
+        }
+        assert!(called.get());
+
+        let derived = eval("Promise.resolve(1)")
+            .to_promise()
+            .unwrap()
+            .finally(|_ctx| Err(Error::ValueError("finally".to_owned())))
+            .unwrap();
+        assert!(derived.resolve_sync().is_err());
+    }
 }
@@ -40,6 +40,16 @@ extern "C" {
     fn JS_Find_Atom_real(ctx: *mut JSContext, name: *const c_char) -> JSAtom ;
     fn Find_Export_Entry_real(ctx: *mut JSContext, m: *mut JSModuleDef, export_name: JSAtom) -> *mut JSExportEntry;
     fn JS_Find_Loaded_Module_real(ctx: *mut JSContext, name: JSAtom) -> *mut JSModuleDef;
+    fn JS_IsArrayBuffer_real(obj: JSValue) -> bool;
+    fn JS_IsMap_real(obj: JSValue) -> bool;
+    fn JS_IsSet_real(obj: JSValue) -> bool;
+    fn JS_GetTypedArrayType_real(obj: JSValue) -> ::std::os::raw::c_int;
+
+    // Not declared in quickjs.h, but exported by quickjs.c.
+    #[link_name = "JS_IsUncatchableError"]
+    fn JS_IsUncatchableError_real(ctx: *mut JSContext, val: JSValue) -> ::std::os::raw::c_int;
+    #[link_name = "JS_SetUncatchableError"]
+    fn JS_SetUncatchableError_real(ctx: *mut JSContext, val: JSValue, flag: ::std::os::raw::c_int);
 }
 
 /// Increment the refcount of this value
@@ -167,6 +177,57 @@ pub unsafe fn JS_IsObject(v: JSValue) -> bool {
     JS_IsObject_real(v)
 }
 
+/// check if a JSValue is an `Error` which scripts can not catch, like the exception thrown
+/// by the interrupt handler
+///
+/// # Safety
+/// `ctx` must be a valid context and `val` a value of its runtime.
+pub unsafe fn JS_IsUncatchableError(ctx: *mut JSContext, val: JSValue) -> bool {
+    JS_IsUncatchableError_real(ctx, val) != 0
+}
+
+/// make an `Error` object uncatchable by scripts, other values are left unchanged
+///
+/// # Safety
+/// `ctx` must be a valid context and `val` a value of its runtime.
+pub unsafe fn JS_SetUncatchableError(ctx: *mut JSContext, val: JSValue, flag: bool) {
+    JS_SetUncatchableError_real(ctx, val, flag as ::std::os::raw::c_int)
+}
+
+/// check if a JSValue is an `ArrayBuffer`, objects only inheriting from
+/// `ArrayBuffer.prototype` and `SharedArrayBuffer`s are not
+///
+/// # Safety
+/// `obj` must be a valid value.
+pub unsafe fn JS_IsArrayBuffer(obj: JSValue) -> bool {
+    JS_IsArrayBuffer_real(obj)
+}
+
+/// check if a JSValue is a `Map`, objects only inheriting from `Map.prototype` are not
+///
+/// # Safety
+/// `obj` must be a valid value.
+pub unsafe fn JS_IsMap(obj: JSValue) -> bool {
+    JS_IsMap_real(obj)
+}
+
+/// check if a JSValue is a `Set`, objects only inheriting from `Set.prototype` are not
+///
+/// # Safety
+/// `obj` must be a valid value.
+pub unsafe fn JS_IsSet(obj: JSValue) -> bool {
+    JS_IsSet_real(obj)
+}
+
+/// the type of a typed array, from 0 for `Uint8ClampedArray` to 10 for `Float64Array` in the
+/// order of their class ids, -1 for other values
+///
+/// # Safety
+/// `obj` must be a valid value.
+pub unsafe fn JS_GetTypedArrayType(obj: JSValue) -> ::std::os::raw::c_int {
+    JS_GetTypedArrayType_real(obj)
+}
+
 /// set a property of an object identified by a JSAtom
 pub unsafe fn JS_SetProperty(
     ctx: *mut JSContext,
@@ -212,6 +273,7 @@ pub fn JS_ToStr<'a>(ctx: *mut JSContext, val: JSValue) -> Cow<'a, str> {
     val.to_string_lossy()
 }
 
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn JS_Equal(ctx: *mut JSContext, one: &JSValue, other: &JSValue) -> bool {
     if one.tag != other.tag {
         return false;
@@ -235,14 +297,32 @@ pub fn JS_Equal(ctx: *mut JSContext, one: &JSValue, other: &JSValue) -> bool {
         crate::ffi::JS_TAG_CATCH_OFFSET => todo!(),
         crate::ffi::JS_TAG_UNINITIALIZED => todo!(),
         crate::ffi::JS_TAG_FUNCTION_BYTECODE => todo!(),
-        #[cfg(feature = "bigint")]
-        ffi::JS_TAG_BIG_INT => JsTag::BigInt,
+        crate::ffi::JS_TAG_BIG_INT => unsafe { big_int_eq(ctx, *one, *other) },
         _other => {
             unreachable!()
         }
     }
 }
 
+/// Compare two BigInt values by their decimal representation, QuickJS exports no BigInt
+/// comparison.
+unsafe fn big_int_eq(ctx: *mut JSContext, one: JSValue, other: JSValue) -> bool {
+    let to_string = |val| {
+        let mut len = 0_usize;
+        let ptr = JS_ToCStringLen2(ctx, &mut len, val, 0);
+        if ptr.is_null() {
+            JS_FreeValue(ctx, JS_GetException(ctx));
+            return None;
+        }
+        let bytes = CStr::from_ptr(ptr).to_bytes().to_vec();
+        JS_FreeCString(ctx, ptr);
+
+        Some(bytes)
+    };
+
+    matches!((to_string(one), to_string(other)), (Some(a), Some(b)) if a == b)
+}
+
 pub const fn JS_MKVAL(tag: i32, val: i32) -> JSValue {
     JSValue {
         u: JSValueUnion { int32: val },
@@ -1,25 +1,42 @@
 use std::{
     any::Any,
     f32::consts,
-    ffi::{c_char, c_void, CStr},
+    ffi::{c_char, c_int, c_void, CStr},
     mem::{size_of, size_of_val},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
 };
 
+use once_cell::sync::Lazy;
+
 use crate::{
-    common::{make_cstring, Error},
+    common::{make_cstring, Error, JsException, JsonParseError},
+    context::{CollectionIntrinsics, Intrinsics},
     ffi::{
         js_free, JSAtom, JSCFunction, JSCFunctionEnum_JS_CFUNC_constructor,
         JSCFunctionEnum_JS_CFUNC_generic, JSCFunctionListEntry, JSCFunctionMagic, JSCFunctionType,
-        JSClassDef, JSClassID, JSContext, JSModuleDef, JSModuleInitFunc, JSValue, JSValueUnion,
-        JS_AddModuleExport, JS_AtomToString, JS_Call, JS_DefinePropertyValue, JS_EvalFunction,
-        JS_GetException, JS_GetModuleName, JS_NewAtomLen, JS_NewCFunction2, JS_NewCModule,
-        JS_NewClass, JS_NewClassID, JS_NewObjectProtoClass, JS_NewObjectWithProto, JS_ReadObject,
-        JS_SetClassProto, JS_SetConstructor, JS_SetModuleExportList, JS_SetPropertyFunctionList,
-        JS_WriteObject, JS_DEF_CFUNC, JS_DEF_CGETSET, JS_PROP_CONFIGURABLE, JS_PROP_WRITABLE,
+        JSClassDef, JSClassID, JSContext, JSModuleDef, JSModuleInitFunc, JSPropertyDescriptor,
+        JSPropertyEnum, JSRuntime, JSValue, JSValueUnion, JS_AddModuleExport, JS_AtomToString,
+        JS_Call, JS_CallConstructor, JS_DefinePropertyGetSet, JS_DefinePropertyValue,
+        JS_DeleteProperty, JS_DetachArrayBuffer, JS_EvalFunction, JS_FreeAtom, JS_FreeCString,
+        JS_FreeValue, JS_GetArrayBuffer, JS_GetException, JS_GetModuleName, JS_GetOpaque,
+        JS_GetOwnProperty, JS_GetOwnPropertyNames, JS_GetPropertyInternal, JS_GetPropertyStr,
+        JS_GetPropertyUint32, JS_GetTypedArrayBuffer, JS_HasProperty, JS_IsError, JS_IsInstanceOf,
+        JS_IsRegisteredClass, JS_IsUncatchableError, JS_JSONStringify, JS_NewArray,
+        JS_NewArrayBuffer, JS_NewArrayBufferCopy, JS_NewAtomLen, JS_NewCFunction2,
+        JS_NewCFunctionData, JS_NewCModule, JS_NewClass, JS_NewClassID, JS_NewError,
+        JS_NewObjectClass, JS_NewObjectProtoClass, JS_NewObjectWithProto, JS_NewPromiseCapability,
+        JS_ParseJSON2, JS_ReadObject, JS_SetClassProto, JS_SetConstructor, JS_SetModuleExportList,
+        JS_SetProperty, JS_SetPropertyFunctionList, JS_SetPropertyUint32, JS_SetUncatchableError,
+        JS_Throw, JS_ThrowInternalError, JS_ThrowOutOfMemory, JS_ThrowRangeError,
+        JS_ThrowSyntaxError, JS_ThrowTypeError, JS_ToCStringLen2, JS_WriteObject, JS_DEF_CFUNC,
+        JS_DEF_CGETSET, JS_GPN_ENUM_ONLY, JS_GPN_STRING_MASK, JS_PARSE_JSON_EXT,
+        JS_PROP_CONFIGURABLE, JS_PROP_ENUMERABLE, JS_PROP_GETSET, JS_PROP_WRITABLE,
         JS_READ_OBJ_BYTECODE, JS_WRITE_OBJ_BYTECODE,
     },
-    Context, JSCGetter, JSCSetter, JsAtom, JsCompiledFunction, JsFunction, JsModuleDef, JsString,
-    JsValue, JS_UNDEFINED,
+    Args, Context, IntoPropertyKey, JSCGetter, JSCSetter, JsArray, JsAtom, JsCompiledFunction,
+    JsFunction, JsModuleDef, JsNumber, JsPromise, JsString, JsValue, PropertyDescriptor,
+    PropertyKey, TypedArrayElement, JS_EXCEPTION, JS_UNDEFINED,
 };
 
 pub fn js_eval<'a>(
@@ -65,23 +82,457 @@ pub fn get_last_exception<'a>(ctx: &Context) -> Option<Error> {
 
     if value.is_null() {
         None
+    } else if unsafe { JS_IsUncatchableError(ctx.inner, value.inner) } {
+        // 只有中断时抛出的异常不能被脚本捕获
+        Some(Error::Interrupted)
     } else if value.is_exception() {
         Some(Error::GeneralError(
             "Could get exception from runtime".into(),
         ))
     } else {
-        match value.to_string() {
-            Ok(strval) => {
-                let val = strval.value();
-                if val.contains("out of memory") {
-                    Some(Error::OutOfMemoryError)
-                } else {
-                    Some(Error::GeneralError(val.to_string()))
-                }
+        let exception = new_exception(ctx, &value);
+        if exception.is_error && exception.message.contains("out of memory") {
+            Some(Error::OutOfMemoryError)
+        } else {
+            Some(Error::JsException(Box::new(exception)))
+        }
+    }
+}
+
+/// Capture the name, message, stack, cause and custom properties of a thrown value.
+/// The thrown value itself is kept by the runtime, see [`JsException::value`].
+pub fn new_exception(ctx: &Context, value: &JsValue) -> JsException {
+    let is_error = unsafe { JS_IsError(ctx.inner, value.inner) == 1 };
+
+    let mut exception = JsException {
+        is_error,
+        name: None,
+        message: String::new(),
+        stack: None,
+        cause: None,
+        properties: Vec::new(),
+        value: Arc::new(
+            ctx.get_runtime()
+                .state()
+                .store_exception(ctx.get_runtime().inner, value.dup_value()),
+        ),
+    };
+
+    if is_error {
+        exception.name = exception_property(ctx, value, "name");
+        exception.message = exception_property(ctx, value, "message").unwrap_or_default();
+        exception.stack = exception_property(ctx, value, "stack");
+        exception.cause = exception_property(ctx, value, "cause");
+    } else {
+        exception.message = to_string_lossy(ctx, value.inner).unwrap_or_else(|| {
+            clear_exception(ctx);
+            format!("<{:?}>", value.tag())
+        });
+    }
+
+    if value.is_object() {
+        exception.properties =
+            own_property_names(ctx, value, JS_GPN_STRING_MASK | JS_GPN_ENUM_ONLY)
+                .into_iter()
+                .filter(|name| !matches!(name.as_str(), "name" | "message" | "stack" | "cause"))
+                .filter_map(|name| {
+                    let val = exception_property(ctx, value, &name)?;
+                    Some((name, val))
+                })
+                .collect();
+    }
+
+    exception
+}
+
+/// Read a property as string while an exception is being captured, failures are discarded
+/// so they do not replace the exception.
+fn exception_property(ctx: &Context, obj: &JsValue, name: &str) -> Option<String> {
+    let cname = make_cstring(name).ok()?;
+    let val = unsafe { JS_GetPropertyStr(ctx.inner, obj.inner, cname.as_ptr()) };
+    let val = JsValue::new(ctx, val);
+
+    if val.is_exception() {
+        clear_exception(ctx);
+        None
+    } else if val.is_undefined() {
+        None
+    } else {
+        to_string_lossy(ctx, val.inner).or_else(|| {
+            clear_exception(ctx);
+            None
+        })
+    }
+}
+
+/// Discard the pending exception.
+fn clear_exception(ctx: &Context) {
+    unsafe { JS_FreeValue(ctx.inner, JS_GetException(ctx.inner)) };
+}
+
+/// Own property names of `obj` selected by the `JS_GPN_*` flags, symbols are skipped.
+pub(crate) fn own_property_names(ctx: &Context, obj: &JsValue, flags: u32) -> Vec<String> {
+    let mut tab: *mut JSPropertyEnum = std::ptr::null_mut();
+    let mut len = 0_u32;
+    let rst =
+        unsafe { JS_GetOwnPropertyNames(ctx.inner, &mut tab, &mut len, obj.inner, flags as i32) };
+    if rst < 0 {
+        clear_exception(ctx);
+        return Vec::new();
+    }
+
+    let mut names = Vec::with_capacity(len as usize);
+    unsafe {
+        for item in std::slice::from_raw_parts(tab, len as usize) {
+            let name = atom_to_string(ctx, item.atom);
+            if let Some(name) = to_string_lossy(ctx, name.inner) {
+                names.push(name);
             }
-            Err(e) => Some(e),
+            JS_FreeAtom(ctx.inner, item.atom);
         }
+        js_free(ctx.inner, tab as *mut c_void);
+    }
+
+    names
+}
+
+/// Own property keys of `obj` selected by the `JS_GPN_*` flags.
+pub fn own_property_atoms<'a>(
+    ctx: &'a Context,
+    obj: &JsValue,
+    flags: u32,
+) -> Result<Vec<JsAtom<'a>>, Error> {
+    let mut tab: *mut JSPropertyEnum = std::ptr::null_mut();
+    let mut len = 0_u32;
+    let rst =
+        unsafe { JS_GetOwnPropertyNames(ctx.inner, &mut tab, &mut len, obj.inner, flags as i32) };
+    if rst < 0 {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::PropertyError("Could not get own property names".into())))?
+    }
+
+    // NOTE: the atoms of the table are owned by the returned keys.
+    let keys = unsafe {
+        let keys = std::slice::from_raw_parts(tab, len as usize)
+            .iter()
+            .map(|item| JsAtom::new(ctx, item.atom))
+            .collect();
+        js_free(ctx.inner, tab as *mut c_void);
+        keys
+    };
+
+    Ok(keys)
+}
+
+/// Get the property `key` of `obj`, looking up the prototype chain.
+pub fn get_property<'a>(
+    ctx: &'a Context,
+    obj: &JsValue,
+    key: &PropertyKey,
+) -> Result<JsValue<'a>, Error> {
+    let val = match key {
+        PropertyKey::Index(idx) => unsafe { JS_GetPropertyUint32(ctx.inner, obj.inner, *idx) },
+        PropertyKey::Atom(atom) => unsafe {
+            JS_GetPropertyInternal(ctx.inner, obj.inner, atom.inner, obj.inner, 0)
+        },
+    };
+    let val = JsValue::new(ctx, val);
+    assert_exception(ctx, &val, "Could not get property")?;
+
+    Ok(val)
+}
+
+/// Set the property `key` of `obj` like an assignment, setters are called.
+pub fn set_property(
+    ctx: &Context,
+    obj: &JsValue,
+    key: &PropertyKey,
+    value: JsValue,
+) -> Result<(), Error> {
+    // NOTE: JS_SetPropertyUint32 and JS_SetProperty take ownership of the value.
+    let rst = match key {
+        PropertyKey::Index(idx) => unsafe {
+            JS_SetPropertyUint32(ctx.inner, obj.inner, *idx, value.forget())
+        },
+        PropertyKey::Atom(atom) => unsafe {
+            JS_SetProperty(ctx.inner, obj.inner, atom.inner, value.forget())
+        },
+    };
+    if rst < 0 {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::PropertyError("Could not set property".into())))?
+    }
+
+    Ok(())
+}
+
+/// Check if `obj` or its prototype chain has the property `prop`, like the `in` operator.
+pub fn has_property(ctx: &Context, obj: &JsValue, prop: &JsAtom) -> Result<bool, Error> {
+    let rst = unsafe { JS_HasProperty(ctx.inner, obj.inner, prop.inner) };
+    if rst < 0 {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::PropertyError("Could not check property".into())))?
     }
+
+    Ok(rst == 1)
+}
+
+/// Delete the own property `prop` of `obj`, `false` if it is not configurable.
+pub fn delete_property(ctx: &Context, obj: &JsValue, prop: &JsAtom) -> Result<bool, Error> {
+    let rst = unsafe { JS_DeleteProperty(ctx.inner, obj.inner, prop.inner, 0) };
+    if rst < 0 {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::PropertyError("Could not delete property".into())))?
+    }
+
+    Ok(rst == 1)
+}
+
+/// Get the descriptor of the own property `prop` of `obj`, `None` if there is no such property.
+pub fn get_own_property<'a>(
+    ctx: &'a Context,
+    obj: &JsValue,
+    prop: &JsAtom,
+) -> Result<Option<PropertyDescriptor<'a>>, Error> {
+    let mut desc = JSPropertyDescriptor {
+        flags: 0,
+        value: JS_UNDEFINED,
+        getter: JS_UNDEFINED,
+        setter: JS_UNDEFINED,
+    };
+    let rst = unsafe { JS_GetOwnProperty(ctx.inner, &mut desc, obj.inner, prop.inner) };
+    if rst < 0 {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::PropertyError("Could not get own property".into())))?
+    }
+    if rst == 0 {
+        return Ok(None);
+    }
+
+    // NOTE: the values of the descriptor are owned by the caller.
+    let value = JsValue::new(ctx, desc.value);
+    let getter = JsValue::new(ctx, desc.getter);
+    let setter = JsValue::new(ctx, desc.setter);
+    let flags = desc.flags as u32;
+    let is_accessor = flags & JS_PROP_GETSET != 0;
+    let defined = |val: JsValue<'a>| (!val.is_undefined()).then_some(val);
+
+    Ok(Some(PropertyDescriptor {
+        value: (!is_accessor).then_some(value),
+        getter: defined(getter),
+        setter: defined(setter),
+        writable: flags & JS_PROP_WRITABLE != 0,
+        enumerable: flags & JS_PROP_ENUMERABLE != 0,
+        configurable: flags & JS_PROP_CONFIGURABLE != 0,
+    }))
+}
+
+/// Convert a value to a Rust string like `String(value)`, `None` if the conversion threw.
+pub(crate) fn to_string_lossy(ctx: &Context, val: JSValue) -> Option<String> {
+    let mut len = 0;
+    let ptr = unsafe { JS_ToCStringLen2(ctx.inner, &mut len, val, 0) };
+    if ptr.is_null() {
+        return None;
+    }
+
+    let rst = unsafe {
+        let bytes = std::slice::from_raw_parts(ptr as *const u8, len);
+        let s = String::from_utf8_lossy(bytes).into_owned();
+        JS_FreeCString(ctx.inner, ptr);
+        s
+    };
+
+    Some(rst)
+}
+
+/// Convert any value to a Rust string like `String(value)` does.
+pub fn js_to_string(ctx: &Context, val: &JsValue) -> Result<String, Error> {
+    if let Some(rst) = to_string_lossy(ctx, val.inner) {
+        Ok(rst)
+    } else if let Some(err) = get_last_exception(ctx) {
+        Err(err)
+    } else {
+        Err(Error::ValueError("js_to_string() failed".to_owned()))
+    }
+}
+
+/// Throw a `TypeError`, returns `JS_EXCEPTION` so native callbacks can return it directly.
+pub fn throw_type_error(ctx: &Context, msg: &str) -> JSValue {
+    let msg = make_cstring(msg.replace('\0', "")).expect("make_cstring() failed");
+    unsafe { JS_ThrowTypeError(ctx.inner, c"%s".as_ptr(), msg.as_ptr()) }
+}
+
+/// Throw a `RangeError`, returns `JS_EXCEPTION`.
+pub fn throw_range_error(ctx: &Context, msg: &str) -> JSValue {
+    let msg = make_cstring(msg.replace('\0', "")).expect("make_cstring() failed");
+    unsafe { JS_ThrowRangeError(ctx.inner, c"%s".as_ptr(), msg.as_ptr()) }
+}
+
+/// Throw a `SyntaxError`, returns `JS_EXCEPTION`.
+pub fn throw_syntax_error(ctx: &Context, msg: &str) -> JSValue {
+    let msg = make_cstring(msg.replace('\0', "")).expect("make_cstring() failed");
+    unsafe { JS_ThrowSyntaxError(ctx.inner, c"%s".as_ptr(), msg.as_ptr()) }
+}
+
+/// Throw an `InternalError`, returns `JS_EXCEPTION`.
+pub fn throw_internal_error(ctx: &Context, msg: &str) -> JSValue {
+    let msg = make_cstring(msg.replace('\0', "")).expect("make_cstring() failed");
+    unsafe { JS_ThrowInternalError(ctx.inner, c"%s".as_ptr(), msg.as_ptr()) }
+}
+
+/// Throw an arbitrary value, returns `JS_EXCEPTION`.
+pub fn throw_value(ctx: &Context, val: JsValue) -> JSValue {
+    // NOTE: JS_Throw takes ownership of the value.
+    unsafe { JS_Throw(ctx.inner, val.forget()) }
+}
+
+/// Throw a Rust error as a JS exception, returns `JS_EXCEPTION`.
+///
+/// A captured [`JsException`] rethrows the original value if it was captured in this runtime,
+/// [`Error::BadType`] becomes a `TypeError` and the other errors a plain `Error`.
+pub fn throw_error(ctx: &Context, err: Error) -> JSValue {
+    match err {
+        Error::JsException(ex) => match ex.value(ctx) {
+            Some(val) => throw_value(ctx, val),
+            None => throw_error_object(ctx, ex.name().unwrap_or("Error"), ex.message()),
+        },
+        Error::Interrupted => unsafe {
+            // Like the interrupt handler, the exception can not be caught by the script and
+            // is reported as an interruption once it reaches the caller.
+            throw_internal_error(ctx, "interrupted");
+            let val = JS_GetException(ctx.inner);
+            JS_SetUncatchableError(ctx.inner, val, true);
+            JS_Throw(ctx.inner, val)
+        },
+        Error::OutOfMemoryError => unsafe { JS_ThrowOutOfMemory(ctx.inner) },
+        Error::BadType(msg) => throw_type_error(ctx, &msg),
+        err => throw_error_object(ctx, "Error", &err.to_string()),
+    }
+}
+
+fn throw_error_object(ctx: &Context, name: &str, msg: &str) -> JSValue {
+    let obj = JsValue::new(ctx, unsafe { JS_NewError(ctx.inner) });
+    if obj.is_exception() {
+        return JS_EXCEPTION;
+    }
+
+    let flags = (JS_PROP_WRITABLE | JS_PROP_CONFIGURABLE) as i32;
+    let _ = obj.define_property("message", ctx.get_string(msg), flags);
+    if name != "Error" {
+        let _ = obj.define_property("name", ctx.get_string(name), flags);
+    }
+
+    throw_value(ctx, obj)
+}
+
+/// Convert the result of a native callback to the value returned to QuickJS,
+/// errors are thrown with [`throw_error`].
+pub fn callback_result(ctx: &Context, rst: Result<JsValue, Error>) -> JSValue {
+    match rst {
+        Ok(val) => unsafe { val.forget() },
+        Err(err) => throw_error(ctx, err),
+    }
+}
+
+/// Signature of the Rust closures exposed to scripts with [`new_function`].
+pub type RustFunction =
+    dyn for<'c> Fn(&'c Context<'c>, JsValue<'c>, Args<'c>) -> Result<JsValue<'c>, Error>;
+
+static CLOSURE_CLASS_ID: Lazy<JSClassID> = Lazy::new(|| {
+    let mut tmp = 0;
+    new_class_id(&mut tmp)
+});
+
+const CLOSURE_CLASS_DEF: JSClassDef = JSClassDef {
+    class_name: c"RustClosure".as_ptr(),
+    finalizer: Some(closure_finalizer),
+    gc_mark: None,
+    call: None,
+    exotic: std::ptr::null_mut(),
+};
+
+unsafe extern "C" fn closure_finalizer(_rt: *mut JSRuntime, val: JSValue) {
+    let closure = JS_GetOpaque(val, *CLOSURE_CLASS_ID) as *mut Box<RustFunction>;
+    if !closure.is_null() {
+        drop(Box::from_raw(closure));
+    }
+}
+
+unsafe extern "C" fn closure_trampoline(
+    ctx: *mut JSContext,
+    this_val: JSValue,
+    argc: c_int,
+    argv: *mut JSValue,
+    _magic: c_int,
+    func_data: *mut JSValue,
+) -> JSValue {
+    let closure = JS_GetOpaque(*func_data, *CLOSURE_CLASS_ID) as *const Box<RustFunction>;
+
+    Context::with_raw(ctx, |ctx| {
+        // this_val 由调用方持有，转成 JsValue 前需要增加引用计数
+        let this = JsValue::new(ctx, this_val);
+        this.increment_ref_count();
+        let args = Args::from_raw(ctx, argc, argv);
+
+        catch_callback(ctx, || (*closure)(ctx, this, args))
+    })
+}
+
+/// Run a native callback, converting its result with [`callback_result`] and a Rust panic
+/// to an `InternalError`, unwinding into QuickJS frames is undefined behavior.
+pub(crate) fn catch_callback<'a>(
+    ctx: &'a Context,
+    f: impl FnOnce() -> Result<JsValue<'a>, Error>,
+) -> JSValue {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(rst) => callback_result(ctx, rst),
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            throw_internal_error(ctx, &format!("Rust function panicked: {msg}"))
+        }
+    }
+}
+
+/// Create a JS function calling the Rust closure `func`.
+///
+/// The closure is dropped when the function is garbage-collected, a panic inside it is
+/// caught and thrown to the script as an `InternalError`.
+pub fn new_function<'a, F>(ctx: &'a Context, name: &str, func: F) -> Result<JsValue<'a>, Error>
+where
+    F: for<'c> Fn(&'c Context<'c>, JsValue<'c>, Args<'c>) -> Result<JsValue<'c>, Error> + 'static,
+{
+    let rt = ctx.get_runtime().inner;
+    if unsafe { JS_IsRegisteredClass(rt, *CLOSURE_CLASS_ID) } == 0 {
+        new_class(ctx, *CLOSURE_CLASS_ID, &CLOSURE_CLASS_DEF)?;
+    }
+
+    let data = JsValue::new(ctx, unsafe {
+        JS_NewObjectClass(ctx.inner, *CLOSURE_CLASS_ID as i32)
+    });
+    assert_exception(ctx, &data, "new_function() failed to create closure data")?;
+    let closure: Box<Box<RustFunction>> = Box::new(Box::new(func));
+    data.set_opaque(Box::into_raw(closure) as *mut c_void);
+
+    // NOTE: JS_NewCFunctionData duplicates the data values.
+    let mut func_data = [data.inner];
+    let func = JsValue::new(ctx, unsafe {
+        JS_NewCFunctionData(
+            ctx.inner,
+            Some(closure_trampoline),
+            0,
+            0,
+            1,
+            func_data.as_mut_ptr(),
+        )
+    });
+    assert_exception(ctx, &func, "new_function() failed")?;
+    func.define_property("name", ctx.get_string(name), JS_PROP_CONFIGURABLE as i32)?;
+
+    Ok(func)
 }
 
 /// compile a script, will result in a JSValueRef with tag JS_TAG_FUNCTION_BYTECODE or JS_TAG_MODULE.
@@ -217,6 +668,13 @@ pub fn new_object_with_proto<'a>(
     Ok(val)
 }
 
+pub fn new_array<'a>(ctx: &'a Context) -> Result<JsArray<'a>, Error> {
+    let val = JsValue::new(ctx, unsafe { JS_NewArray(ctx.inner) });
+    assert_exception(ctx, &val, "Could not create array")?;
+
+    val.try_into()
+}
+
 pub fn new_atom<'a>(ctx: &'a Context, name: &str) -> Result<JsAtom<'a>, Error> {
     let name = make_cstring(name)?;
 
@@ -274,6 +732,42 @@ pub fn define_property(
     Ok(())
 }
 
+/// Define an accessor property, `getter` and `setter` are JS functions.
+pub fn define_property_getset(
+    ctx: &Context,
+    this_obj: &JsValue,
+    prop_name: JsAtom,
+    getter: Option<JsValue>,
+    setter: Option<JsValue>,
+    flags: i32,
+) -> Result<(), Error> {
+    // NOTE: JS_DefinePropertyGetSet takes ownership of the getter and the setter.
+    let getter = getter.map_or(JS_UNDEFINED, |val| unsafe { val.forget() });
+    let setter = setter.map_or(JS_UNDEFINED, |val| unsafe { val.forget() });
+    let val = unsafe {
+        JS_DefinePropertyGetSet(
+            ctx.inner,
+            this_obj.inner,
+            prop_name.inner,
+            getter,
+            setter,
+            flags,
+        )
+    };
+
+    if val == -1 {
+        if let Some(err) = get_last_exception(ctx) {
+            Err(err)?
+        } else {
+            Err(Error::GeneralError(
+                "define_property_getset() is failed".to_string(),
+            ))?
+        }
+    }
+
+    Ok(())
+}
+
 pub fn assert_exception(ctx: &Context, val: &JsValue, err_msg: &str) -> Result<(), Error> {
     Ok(if val.is_exception() {
         let rst = get_last_exception(ctx);
@@ -308,6 +802,394 @@ pub fn call_js_function<'a>(
     Ok(val)
 }
 
+/// Call `ctor` as a constructor, like `new ctor(...args)`.
+pub fn call_constructor<'a>(
+    ctx: &'a Context,
+    ctor: &JsValue,
+    args: &[&JsValue],
+) -> Result<JsValue<'a>, Error> {
+    let mut qargs = args.iter().map(|a| a.inner).collect::<Vec<_>>();
+    let val =
+        unsafe { JS_CallConstructor(ctx.inner, ctor.inner, args.len() as i32, qargs.as_mut_ptr()) };
+    let val = JsValue::new(ctx, val);
+    assert_exception(ctx, &val, "call_constructor() is failed")?;
+
+    Ok(val)
+}
+
+/// Call the method `name` of `this`, like `this[name](...args)`.
+pub fn call_method<'a>(
+    ctx: &'a Context,
+    this: &JsValue,
+    name: &str,
+    args: &[&JsValue],
+) -> Result<JsValue<'a>, Error> {
+    let func = this
+        .get_property(name)
+        .filter(|func| func.is_function())
+        .ok_or_else(|| Error::PropertyError(format!("{name} is not a function")))?;
+
+    call_js_function(ctx, &func, Some(this), args)
+}
+
+/// A builtin method of `Map` and `Set`, see [`call_map_method`].
+pub enum MapMethod<'v> {
+    /// `set(key, value)` of `Map` or `add(key)` of `Set`.
+    Set(&'v JsValue<'v>, &'v JsValue<'v>),
+    /// `get(key)`, only for `Map`.
+    Get(&'v JsValue<'v>),
+    Has(&'v JsValue<'v>),
+    Delete(&'v JsValue<'v>),
+    Clear,
+    Size,
+    /// An iterator over the keys of a `Map` or the values of a `Set`.
+    Iterator,
+}
+
+fn collection_intrinsics(intrinsics: &Intrinsics, is_set: bool) -> &CollectionIntrinsics {
+    if is_set {
+        &intrinsics.set
+    } else {
+        &intrinsics.map
+    }
+}
+
+/// Create an empty `Map`, or a `Set` if `is_set`, even if scripts replaced the globals.
+pub fn new_map<'a>(ctx: &'a Context, is_set: bool) -> Result<JsValue<'a>, Error> {
+    let ctor = ctx.intrinsic(|intrinsics| collection_intrinsics(intrinsics, is_set).constructor)?;
+
+    call_constructor(ctx, &ctor, &[])
+}
+
+/// Call the builtin `method` of `Map`, or `Set` if `is_set`, on `obj`. The methods are
+/// captured when the context is created so scripts can not replace them, `obj` must be of
+/// the class or a `TypeError` is returned.
+pub fn call_map_method<'a>(
+    ctx: &'a Context,
+    obj: &JsValue,
+    is_set: bool,
+    method: MapMethod,
+) -> Result<JsValue<'a>, Error> {
+    let func = ctx.intrinsic(|intrinsics| {
+        let builtins = collection_intrinsics(intrinsics, is_set);
+        match method {
+            MapMethod::Set(..) => builtins.set,
+            MapMethod::Get(_) => builtins.get,
+            MapMethod::Has(_) => builtins.has,
+            MapMethod::Delete(_) => builtins.delete,
+            MapMethod::Clear => builtins.clear,
+            MapMethod::Size => builtins.size,
+            MapMethod::Iterator => builtins.keys,
+        }
+    })?;
+    let args = match method {
+        MapMethod::Set(key, value) => vec![key, value],
+        MapMethod::Get(key) | MapMethod::Has(key) | MapMethod::Delete(key) => vec![key],
+        MapMethod::Clear | MapMethod::Size | MapMethod::Iterator => vec![],
+    };
+
+    call_js_function(ctx, &func, Some(obj), &args)
+}
+
+/// Step the iterator `iter` created by [`MapMethod::Iterator`], `None` once it is done.
+pub fn map_iterator_next<'a>(
+    ctx: &'a Context,
+    iter: &JsValue,
+    is_set: bool,
+) -> Result<Option<JsValue<'a>>, Error> {
+    let next = ctx.intrinsic(|intrinsics| collection_intrinsics(intrinsics, is_set).next)?;
+    // 结果是引擎新建的 { value, done }，读取其自身属性不会经过脚本
+    let rst = call_js_function(ctx, &next, Some(iter), &[])?;
+    let done = get_property(ctx, &rst, &"done".into_key(ctx)?)?;
+    if crate::FromJs::from_js(ctx, done)? {
+        return Ok(None);
+    }
+
+    get_property(ctx, &rst, &"value".into_key(ctx)?).map(Some)
+}
+
+/// Create a `Date` at `millis` milliseconds since the Unix epoch, even if scripts replaced
+/// the global `Date`.
+pub fn new_date<'a>(ctx: &'a Context, millis: f64) -> Result<JsValue<'a>, Error> {
+    let ctor = ctx.intrinsic(|intrinsics| intrinsics.date)?;
+    let millis = JsNumber::new(ctx, millis).to_value();
+
+    call_constructor(ctx, &ctor, &[&millis])
+}
+
+/// The time value of the `Date` `val`, like `val.getTime()`, `None` if `val` is not a `Date`.
+/// The builtin `getTime` is used, it checks the class of `val` instead of its prototype.
+pub fn date_time_value(ctx: &Context, val: &JsValue) -> Result<Option<f64>, Error> {
+    let get_time = ctx.intrinsic(|intrinsics| intrinsics.date_get_time)?;
+    let millis = unsafe {
+        JS_Call(
+            ctx.inner,
+            get_time.inner,
+            val.inner,
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    let millis = JsValue::new(ctx, millis);
+    if millis.is_exception() {
+        clear_exception(ctx);
+        return Ok(None);
+    }
+
+    crate::FromJs::from_js(ctx, millis).map(Some)
+}
+
+/// Get the global constructor `name`, like `globalThis[name]`.
+pub fn global_constructor<'a>(ctx: &'a Context, name: &str) -> Result<JsValue<'a>, Error> {
+    get_global_object(ctx)
+        .get_property(name)
+        .filter(|ctor| ctor.is_function())
+        .ok_or_else(|| Error::PropertyError(format!("{name} is not found")))
+}
+
+/// `val instanceof ctor`
+pub fn is_instance_of(ctx: &Context, val: &JsValue, ctor: &JsValue) -> Result<bool, Error> {
+    let rst = unsafe { JS_IsInstanceOf(ctx.inner, val.inner, ctor.inner) };
+    if rst < 0 {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::GeneralError("is_instance_of() is failed".to_owned())))?
+    }
+
+    Ok(rst > 0)
+}
+
+/// Create an `ArrayBuffer` over the memory of `data` without copying it, `data` is dropped
+/// once the buffer is detached or collected.
+pub fn new_array_buffer<'a, T: TypedArrayElement>(
+    ctx: &'a Context,
+    data: Vec<T>,
+) -> Result<JsValue<'a>, Error> {
+    unsafe extern "C" fn free_data<T>(_rt: *mut JSRuntime, opaque: *mut c_void, ptr: *mut c_void) {
+        // NOTE: 分离时已经释放，回收时 QuickJS 以空指针再次调用
+        if !ptr.is_null() {
+            drop(Box::from_raw(opaque as *mut Vec<T>));
+        }
+    }
+
+    let len = size_of_val(data.as_slice());
+    // NOTE: 只移动 Vec 本身，元素所在的内存不变
+    let mut data = Box::new(data);
+    let ptr = data.as_mut_ptr() as *mut u8;
+    let opaque = Box::into_raw(data);
+
+    let val =
+        unsafe { JS_NewArrayBuffer(ctx.inner, ptr, len, Some(free_data::<T>), opaque as _, 0) };
+    let val = JsValue::new(ctx, val);
+    if val.is_exception() {
+        // 创建失败时 QuickJS 不会调用释放函数
+        drop(unsafe { Box::from_raw(opaque) });
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::GeneralError("new_array_buffer() is failed".to_owned())))?
+    }
+
+    Ok(val)
+}
+
+/// Create an `ArrayBuffer` holding a copy of `data`.
+pub fn new_array_buffer_copy<'a>(ctx: &'a Context, data: &[u8]) -> Result<JsValue<'a>, Error> {
+    let val = unsafe { JS_NewArrayBufferCopy(ctx.inner, data.as_ptr(), data.len()) };
+    let val = JsValue::new(ctx, val);
+    assert_exception(ctx, &val, "new_array_buffer_copy() is failed")?;
+
+    Ok(val)
+}
+
+/// Get the memory and the byte length of the `ArrayBuffer` `val`, fails if it is detached.
+pub fn get_array_buffer(ctx: &Context, val: &JsValue) -> Result<(*mut u8, usize), Error> {
+    let mut len = 0;
+    let ptr = unsafe { JS_GetArrayBuffer(ctx.inner, &mut len, val.inner) };
+    if ptr.is_null() {
+        Err(get_last_exception(ctx)
+            .unwrap_or_else(|| Error::ValueError("ArrayBuffer has no data".to_owned())))?
+    }
+
+    Ok((ptr, len))
+}
+
+/// Get the `ArrayBuffer`, the byte offset and the byte length of the typed array `val`.
+pub fn get_typed_array_buffer<'a>(
+    ctx: &'a Context,
+    val: &JsValue,
+) -> Result<(JsValue<'a>, usize, usize), Error> {
+    let (mut offset, mut len) = (0, 0);
+    let buf = unsafe {
+        JS_GetTypedArrayBuffer(
+            ctx.inner,
+            val.inner,
+            &mut offset,
+            &mut len,
+            std::ptr::null_mut(),
+        )
+    };
+    let buf = JsValue::new(ctx, buf);
+    assert_exception(ctx, &buf, "get_typed_array_buffer() is failed")?;
+
+    Ok((buf, offset, len))
+}
+
+/// Detach the `ArrayBuffer` `val`, its memory is freed and its views become empty.
+pub fn detach_array_buffer(ctx: &Context, val: &JsValue) {
+    unsafe { JS_DetachArrayBuffer(ctx.inner, val.inner) }
+}
+
+/// Parse the JSON text `json`, `flags` may be `JS_PARSE_JSON_EXT` to accept the extended
+/// syntax of QuickJS (comments, trailing commas, unquoted keys, ...).
+pub fn parse_json<'a>(ctx: &'a Context, json: &str, flags: i32) -> Result<JsValue<'a>, Error> {
+    let (message, line) = match try_parse_json(ctx, json, flags)? {
+        Ok(val) => return Ok(val),
+        Err(syntax_error) => syntax_error,
+    };
+    let column = if flags & JS_PARSE_JSON_EXT as i32 == 0 {
+        Some(json_error_column(ctx, json, line, &message)?)
+    } else {
+        None
+    };
+
+    Err(Error::JsonParse(JsonParseError {
+        message,
+        line,
+        column,
+    }))
+}
+
+/// Parse `json`, a syntax error is returned as its message and line.
+fn try_parse_json<'a>(
+    ctx: &'a Context,
+    json: &str,
+    flags: i32,
+) -> Result<Result<JsValue<'a>, (String, u32)>, Error> {
+    // NOTE: JS_ParseJSON2 needs a zero terminated buffer.
+    let buf = make_cstring(json)?;
+    let file_name = c"<json>";
+    let val = JsValue::new(ctx, unsafe {
+        JS_ParseJSON2(
+            ctx.inner,
+            buf.as_ptr(),
+            json.len(),
+            file_name.as_ptr(),
+            flags,
+        )
+    });
+    if !val.is_exception() {
+        return Ok(Ok(val));
+    }
+
+    let err = get_last_exception(ctx)
+        .unwrap_or_else(|| Error::GeneralError("parse_json() failed".to_string()));
+    let exception = match err {
+        Error::JsException(ex) if ex.name() == Some("SyntaxError") => ex,
+        err => Err(err)?,
+    };
+
+    // 引擎只报告行号，堆栈形如 "    at <json>:3"
+    let line = exception
+        .stack()
+        .and_then(|stack| stack.lines().next())
+        .and_then(|frame| frame.rsplit(':').next())
+        .and_then(|line| line.trim().parse().ok())
+        .unwrap_or(1);
+    Ok(Err((exception.message().to_owned(), line)))
+}
+
+/// Find the 1-based column of a strict JSON syntax error reported at `line`.
+///
+/// The engine only reports the line, so the column is found by parsing prefixes of `json`
+/// cut on that line: once a prefix holds the whole offending token it fails with the same
+/// error whatever follows. Tokens never span lines in strict JSON. The column is the last
+/// character of the offending token, or one past the end of the line when the input ends
+/// too early.
+fn json_error_column(ctx: &Context, json: &str, line: u32, message: &str) -> Result<u32, Error> {
+    let line_start: usize = json
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    let line_text = json[line_start..].split('\n').next().unwrap_or_default();
+
+    // 只在 token 边界截断，不拆开字面量和数字
+    let is_delimiter = |c: char| c.is_whitespace() || ",:[]{}\"".contains(c);
+    let mut chars = line_text.char_indices().peekable();
+    let mut cuts = vec![];
+    while let Some((i, c)) = chars.next() {
+        if is_delimiter(c) || chars.peek().is_none_or(|&(_, next)| is_delimiter(next)) {
+            cuts.push(i + c.len_utf8());
+        }
+    }
+
+    // 截断处可能在字符串内，所以分别接上换行和闭合引号，两者都报同样的错误才算
+    let fails_same = |cut: usize| -> Result<bool, Error> {
+        for suffix in ["\n@", "\"\n@"] {
+            let prefix = format!("{}{suffix}", &json[..line_start + cut]);
+            match try_parse_json(ctx, &prefix, 0)? {
+                Err((msg, l)) if l == line && msg == message => {}
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    };
+
+    let (mut lo, mut hi) = (0, cuts.len());
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if fails_same(cuts[mid])? {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    let column = match cuts.get(lo) {
+        Some(&cut) => line_text[..cut].chars().count(),
+        None => line_text.chars().count() + 1,
+    };
+    Ok(column as u32)
+}
+
+/// Convert `val` to a JSON string like `JSON.stringify(val, replacer, indent)`.
+pub fn json_stringify(
+    ctx: &Context,
+    val: &JsValue,
+    indent: Option<u32>,
+    replacer: Option<&JsValue>,
+) -> Result<String, Error> {
+    let replacer = replacer.map_or(JS_UNDEFINED, |val| val.inner);
+    let indent = match indent {
+        Some(indent) => JsNumber::new(ctx, indent as f64).to_value(),
+        None => JsValue::new(ctx, JS_UNDEFINED),
+    };
+    let rst = JsValue::new(ctx, unsafe {
+        JS_JSONStringify(ctx.inner, val.inner, replacer, indent.inner)
+    });
+    assert_exception(ctx, &rst, "json_stringify() failed")?;
+    if rst.is_undefined() {
+        Err(Error::ValueError(format!(
+            "{:?} value can not be converted to JSON",
+            val.tag()
+        )))?
+    }
+
+    js_to_string(ctx, &rst)
+}
+
+/// Create a pending promise and its `resolve` and `reject` functions.
+pub fn new_promise_capability<'a>(
+    ctx: &'a Context,
+) -> Result<(JsPromise<'a>, JsFunction<'a>, JsFunction<'a>), Error> {
+    let mut funcs = [JS_UNDEFINED; 2];
+    let promise = JsValue::new(ctx, unsafe {
+        JS_NewPromiseCapability(ctx.inner, funcs.as_mut_ptr())
+    });
+    assert_exception(ctx, &promise, "JS_NewPromiseCapability() failed")?;
+
+    let [resolve, reject] = funcs.map(|func| JsValue::new(ctx, func));
+    Ok((promise.try_into()?, resolve.try_into()?, reject.try_into()?))
+}
+
 /// 创建 C 模块，并在模块上关联本地对象初始化方法（该方法会创建所有的本地对象）
 /// 本地方法列表并不会导出，导出需要通过 JS_AddModuleExport() 进行设置
 pub fn new_c_module<'a>(
@@ -546,4 +1428,238 @@ mod tests {
             .unwrap();
         assert_eq!(rst.value(), 7 * 5);
     }
+
+    #[test]
+    fn test_exception() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let script = r#"
+            let err = new RangeError("bad value", { cause: "overflow" });
+            err.code = 42;
+            throw err;
+        "#;
+        let Err(Error::JsException(ex)) = js_eval(ctx, script, "<test>", flags) else {
+            panic!("expected a JsException");
+        };
+        assert!(ex.is_error());
+        assert_eq!(Some("RangeError"), ex.name());
+        assert_eq!("bad value", ex.message());
+        assert_eq!(Some("overflow"), ex.cause());
+        assert_eq!(Some("42"), ex.property("code"));
+        assert!(ex.stack().unwrap().contains("<test>"));
+        assert_eq!("RangeError: bad value", ex.to_string());
+        assert!(ex.value(ctx).unwrap().is_object());
+
+        let Err(Error::JsException(ex2)) = js_eval(ctx, "throw 42", "<test>", flags) else {
+            panic!("expected a JsException");
+        };
+        assert!(!ex2.is_error());
+        assert_eq!(None, ex2.name());
+        assert_eq!("42", ex2.message());
+        assert_eq!(42, ex2.value(ctx).unwrap().to_int().unwrap().value());
+        // Each exception keeps its own thrown value.
+        assert!(ex.value(ctx).unwrap().is_object());
+        drop(ex2);
+        let other_rt = Runtime::default();
+        assert!(ex.value(&Context::new(&other_rt)).is_none());
+
+        // The original value is thrown again.
+        let global_obj = ctx.get_global_object();
+        global_obj
+            .set_property("err", ex.value(ctx).unwrap())
+            .unwrap();
+        let rethrow = ctx
+            .new_function("rethrow", move |_ctx, _this, _args| {
+                Err(Error::JsException(ex.clone()))
+            })
+            .unwrap();
+        global_obj.set_property("rethrow", rethrow).unwrap();
+        let rst = js_eval(
+            ctx,
+            "try { rethrow() } catch (e) { e === err }",
+            "<test>",
+            flags,
+        );
+        assert!(rst.unwrap().to_bool().unwrap().value());
+    }
+
+    unsafe extern "C" fn js_fail(
+        ctx: *mut JSContext,
+        _this_val: JSValue,
+        argc: ::std::os::raw::c_int,
+        _argv: *mut JSValue,
+    ) -> JSValue {
+        Context::with_raw(ctx, |ctx| match argc {
+            0 => ctx.throw_type_error("missing argument"),
+            1 => ctx.throw_range_error("out of range"),
+            2 => ctx.throw_value(ctx.get_int(7)),
+            _ => callback_result(ctx, Err(Error::ValueError("too many".to_owned()))),
+        })
+    }
+
+    #[test]
+    fn test_throw() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let global_obj = ctx.get_global_object();
+        global_obj
+            .set_property("fail", ctx.get_cfunction(js_fail, "fail", 0).unwrap())
+            .unwrap();
+
+        let script = r#"
+            function check(f) {
+                try { f(); } catch (e) {
+                    return e instanceof Error ? `${e.name}: ${e.message}` : `value ${e}`;
+                }
+            }
+            [check(() => fail()), check(() => fail(1)), check(() => fail(1, 2)),
+             check(() => fail(1, 2, 3))].join("|")
+        "#;
+        let rst = js_eval(
+            ctx,
+            script,
+            "<test>",
+            crate::ffi::JS_EVAL_TYPE_GLOBAL as i32,
+        )
+        .unwrap();
+        assert_eq!(
+            "TypeError: missing argument|RangeError: out of range|value 7|Error: Value error: too many",
+            js_to_string(ctx, &rst).unwrap()
+        );
+
+        // An interruption can not be caught, later exceptions are not reported as one.
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let stop = ctx
+            .new_function("stop", |_ctx, _this, _args| Err(Error::Interrupted))
+            .unwrap();
+        global_obj.set_property("stop", stop).unwrap();
+        let rst = js_eval(ctx, "try { stop() } catch (e) {} 1", "<test>", flags);
+        assert!(matches!(rst, Err(Error::Interrupted)));
+        let rst = js_eval(ctx, "throw new TypeError('x')", "<test>", flags);
+        assert!(matches!(rst, Err(Error::JsException(_))));
+    }
+
+    #[test]
+    fn test_new_function() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let dropped = std::rc::Rc::new(std::cell::Cell::new(false));
+        let guard = DropFlag(dropped.clone());
+        let offset = 10;
+        let add = ctx
+            .new_function("add", move |ctx, _this, args| {
+                let _ = &guard;
+                let mut sum = offset;
+                for arg in args {
+                    sum += arg.clone().to_int()?.value();
+                }
+                Ok(ctx.get_int(sum))
+            })
+            .unwrap();
+        let panic = ctx
+            .new_function("panic", |_ctx, _this, _args| panic!("boom"))
+            .unwrap();
+        let global_obj = ctx.get_global_object();
+        global_obj.set_property("add", add).unwrap();
+        global_obj.set_property("boom", panic).unwrap();
+
+        let rst = js_eval(ctx, "add(1, 2, 3)", "<test>", flags).unwrap();
+        assert_eq!(16, rst.to_int().unwrap().value());
+        let rst = js_eval(ctx, "add.name", "<test>", flags).unwrap();
+        assert_eq!("add", js_to_string(ctx, &rst).unwrap());
+
+        let script = "try { add('x') } catch (e) { e.name }";
+        let rst = js_eval(ctx, script, "<test>", flags).unwrap();
+        assert_eq!("TypeError", js_to_string(ctx, &rst).unwrap());
+
+        let Err(Error::JsException(ex)) = js_eval(ctx, "boom()", "<test>", flags) else {
+            panic!("expected a JsException");
+        };
+        assert_eq!(Some("InternalError"), ex.name());
+        assert!(ex.message().contains("boom"));
+        drop(ex);
+
+        assert!(!dropped.get());
+        js_eval(ctx, "add = undefined", "<test>", flags).unwrap();
+        unsafe { crate::ffi::JS_RunGC(rt.inner) };
+        assert!(dropped.get());
+    }
+
+    #[test]
+    fn test_json() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let val = ctx.parse_json(r#"{"a": [1, 2], "b": "x"}"#).unwrap();
+        let a = val.get_property("a").unwrap();
+        assert_eq!(2, a.get_property(1).unwrap().to_int().unwrap().value());
+        assert_eq!(r#"{"a":[1,2],"b":"x"}"#, val.to_json(None, None).unwrap());
+
+        // 严格模式下的语法错误带有行号和列号
+        let Err(Error::JsonParse(err)) = ctx.parse_json("{\n  \"a\": 1,\n  \"b\": x\n}") else {
+            panic!("expected a JSON parse error");
+        };
+        assert_eq!(3, err.line());
+        assert_eq!(Some(8), err.column());
+        assert_eq!("unexpected token: 'x' at line 3, column 8", err.to_string());
+
+        for (json, line, column) in [
+            ("{\"a\": 1,}", 1, 9),
+            ("[1, 2", 1, 6),
+            ("", 1, 1),
+            ("[\"é\", tru]", 1, 9),
+            ("{\n  \"a\" \"b\"\n}", 2, 9),
+        ] {
+            let Err(Error::JsonParse(err)) = ctx.parse_json(json) else {
+                panic!("expected a JSON parse error for {json:?}");
+            };
+            assert_eq!((line, Some(column)), (err.line(), err.column()), "{json:?}");
+        }
+
+        let json = "{\n  // comment\n  a: [1, 2,],\n}";
+        assert!(ctx.parse_json(json).is_err());
+        let val = ctx.parse_json_with(json, true).unwrap();
+        assert_eq!(
+            "{\n  \"a\": [\n    1,\n    2\n  ]\n}",
+            val.to_json(Some(2), None).unwrap()
+        );
+
+        let Err(Error::JsonParse(err)) = ctx.parse_json_with("{\n  a: ]\n}", true) else {
+            panic!("expected a JSON parse error");
+        };
+        assert_eq!(2, err.line());
+        assert_eq!(None, err.column());
+
+        // replacer 数组只保留列出的键
+        let val = js_eval(ctx, "({ a: 1, b: 2, c: 3 })", "<test>", flags).unwrap();
+        let replacer = js_eval(ctx, "['a', 'c']", "<test>", flags).unwrap();
+        assert_eq!(
+            r#"{"a":1,"c":3}"#,
+            val.to_json(None, Some(&replacer)).unwrap()
+        );
+
+        let undefined = js_eval(ctx, "undefined", "<test>", flags).unwrap();
+        assert!(matches!(
+            undefined.to_json(None, None),
+            Err(Error::ValueError(_))
+        ));
+        let cyclic = js_eval(ctx, "let o = {}; o.o = o; o", "<test>", flags).unwrap();
+        assert!(matches!(
+            cyclic.to_json(None, None),
+            Err(Error::JsException(_))
+        ));
+    }
+
+    struct DropFlag(std::rc::Rc<std::cell::Cell<bool>>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
 }
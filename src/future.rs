@@ -0,0 +1,286 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use crate::{
+    common::Error,
+    ffi::{
+        JSContext, JSRuntime, JSValue, JS_Call, JS_FreeValue, JS_FreeValueRT, JS_GetException,
+        JS_GetRuntime,
+    },
+    function::{new_promise_capability, throw_error},
+    Context, IntoJs, JsPromise, JsValue, Runtime, JS_UNDEFINED,
+};
+
+/// A Rust future spawned by [`Context::new_promise_from_future`], it settles its promise
+/// when done.
+pub(crate) type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// The resolving functions of a promise created for a Rust future.
+struct Resolvers {
+    ctx: *mut JSContext,
+    rt: *mut JSRuntime,
+    resolve: JSValue,
+    reject: JSValue,
+}
+
+impl Resolvers {
+    fn settle<V>(&self, rst: Result<V, Error>)
+    where
+        V: for<'c> IntoJs<'c>,
+    {
+        // The resolving functions keep a reference to their context, so it is still alive.
+        unsafe {
+            Context::with_raw(self.ctx, |ctx| {
+                let (func, mut arg) = match rst.and_then(|val| val.into_js(ctx)) {
+                    Ok(val) => (self.resolve, val),
+                    Err(err) => {
+                        // 借助 throw_error 把 Rust 错误转换为 JS 异常对象
+                        throw_error(ctx, err);
+                        let reason = JsValue::new(ctx, JS_GetException(ctx.inner));
+                        (self.reject, reason)
+                    }
+                };
+
+                let ret = JS_Call(ctx.inner, func, JS_UNDEFINED, 1, &mut arg.inner);
+                JS_FreeValue(ctx.inner, ret);
+            })
+        }
+    }
+}
+
+impl Drop for Resolvers {
+    fn drop(&mut self) {
+        // NOTE: freeing the last resolving function may free the context.
+        unsafe {
+            JS_FreeValueRT(self.rt, self.resolve);
+            JS_FreeValueRT(self.rt, self.reject);
+        }
+    }
+}
+
+/// Create a promise settled with the output of `future`, which is polled by the runtime
+/// driver, see [`Runtime::poll_idle`].
+pub fn new_promise_from_future<'a, F, V>(
+    ctx: &'a Context,
+    future: F,
+) -> Result<JsPromise<'a>, Error>
+where
+    F: Future<Output = Result<V, Error>> + 'static,
+    V: for<'c> IntoJs<'c> + 'static,
+{
+    let (promise, resolve, reject) = new_promise_capability(ctx)?;
+    let resolvers = Resolvers {
+        ctx: ctx.inner,
+        rt: unsafe { JS_GetRuntime(ctx.inner) },
+        resolve: unsafe { resolve.to_value().forget() },
+        reject: unsafe { reject.to_value().forget() },
+    };
+
+    let task: Task = Box::pin(async move {
+        let rst = future.await;
+        resolvers.settle(rst);
+    });
+    ctx.get_runtime().state().tasks.borrow_mut().push(task);
+
+    Ok(promise)
+}
+
+/// Future returned by [`Runtime::idle`].
+pub struct Idle<'a> {
+    pub(crate) rt: &'a Runtime,
+}
+
+impl Future for Idle<'_> {
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.rt.poll_idle(cx)
+    }
+}
+
+/// Awaiting a promise drives its runtime with [`Runtime::poll_idle`], so it works with any
+/// executor.
+impl<'a> Future for JsPromise<'a> {
+    type Output = Result<JsValue<'a>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        if let Some(rst) = self.handled_result() {
+            return Poll::Ready(rst);
+        }
+
+        // 其它 promise 的未处理拒绝与这个 promise 无关，留给 run_jobs_until_idle 报告
+        let idle = self.ctx.get_runtime().poll_progress(cx);
+        match (self.handled_result(), idle) {
+            (Some(rst), _) => Poll::Ready(rst),
+            (None, Poll::Ready(Err(err))) => Poll::Ready(Err(err)),
+            (None, Poll::Ready(Ok(()))) => Poll::Ready(Err(Error::ExecuteError(
+                "Promise is pending but no job or future is left to run".to_owned(),
+            ))),
+            (None, Poll::Pending) => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        future::Future,
+        pin::{pin, Pin},
+        rc::Rc,
+        sync::Arc,
+        task::{self, Poll, Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    use crate::{
+        common::Error, function::js_to_string, Context, IntoJs, JsPromise, JsValue, Runtime,
+    };
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// A minimal executor running `future` on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = task::Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(rst) => return rst,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Pending on the first poll, waking itself so the executor polls again.
+    #[derive(Default)]
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    fn add_double(ctx: &Context) {
+        let func = ctx
+            .new_function("double", |ctx, _this, args| {
+                let n: i32 = args.get(0)?;
+                let promise = ctx.new_promise_from_future(async move {
+                    YieldNow::default().await;
+                    if n < 0 {
+                        return Err(Error::ValueError("negative".to_owned()));
+                    }
+                    Ok(n * 2)
+                })?;
+                promise.into_js(ctx)
+            })
+            .unwrap();
+        ctx.get_global_object()
+            .set_property("double", func)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_await_promise() {
+        let rt = Runtime::default();
+        let ctx = &rt.create_context();
+        add_double(ctx);
+
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let code = "(async () => await double(20) + await double(1))()";
+        let promise: JsPromise = ctx.eval(code, "<test>", flags).unwrap().try_into().unwrap();
+        let rst = block_on(promise).unwrap();
+        assert_eq!(42, rst.to_int().unwrap().value());
+
+        let code = "double(-1).catch(e => e.message)";
+        let promise: JsPromise = ctx.eval(code, "<test>", flags).unwrap().try_into().unwrap();
+        let rst = block_on(promise).unwrap();
+        assert_eq!("Value error: negative", js_to_string(ctx, &rst).unwrap());
+
+        let promise: JsPromise = ctx
+            .eval("double(-1)", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(matches!(block_on(promise), Err(Error::JsException(_))));
+
+        let promise: JsPromise = ctx
+            .eval("new Promise(() => {})", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(matches!(block_on(promise), Err(Error::ExecuteError(_))));
+
+        // 无关的未处理拒绝不影响等待的 promise，之后仍会被报告
+        let code = "Promise.reject(new Error('other')); double(3)";
+        let promise: JsPromise = ctx.eval(code, "<test>", flags).unwrap().try_into().unwrap();
+        assert_eq!(6, block_on(promise).unwrap().to_int().unwrap().value());
+        assert!(matches!(
+            rt.run_jobs_until_idle(),
+            Err(Error::JsException(ex)) if ex.message() == "other"
+        ));
+    }
+
+    #[test]
+    fn test_idle() {
+        let rt = Runtime::default();
+        let ctx = &rt.create_context();
+        add_double(ctx);
+
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        ctx.eval(
+            "globalThis.rst = []; double(1).then(v => rst.push(v)); double(2).then(v => rst.push(v));",
+            "<test>",
+            flags,
+        )
+        .unwrap();
+
+        // 没有 JS 代码等待的 future 也会被驱动
+        let done = Rc::new(Cell::new(false));
+        let flag = done.clone();
+        let promise = ctx
+            .new_promise_from_future(async move {
+                YieldNow::default().await;
+                flag.set(true);
+                Ok(())
+            })
+            .unwrap();
+
+        block_on(rt.idle()).unwrap();
+        assert!(done.get());
+        assert!(promise.result().unwrap().unwrap().is_undefined());
+        let rst = ctx.eval("rst.join()", "<test>", flags).unwrap();
+        assert_eq!("2,4", js_to_string(ctx, &rst).unwrap());
+    }
+
+    #[test]
+    fn test_drop_pending_future() {
+        let rt = Runtime::default();
+        let ctx = rt.create_context();
+        let promise = ctx
+            .new_promise_from_future(std::future::pending::<Result<(), Error>>())
+            .unwrap();
+        let _ = JsValue::from(promise);
+
+        // The runtime frees the pending future and its promise.
+        drop(ctx);
+        drop(rt);
+    }
+}
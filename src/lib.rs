@@ -11,14 +11,33 @@
 // include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 // include!("static-functions.rs");
 
+mod args;
+mod class;
 pub mod common;
 mod context;
+mod convert;
 mod data;
 pub mod ffi;
 #[macro_use]
 pub mod function;
+mod future;
+mod loader;
+mod module;
+mod property;
 mod runtime;
+#[cfg(feature = "serde")]
+mod serde;
 
+pub use args::*;
+pub use class::*;
 pub use context::*;
+pub use convert::*;
 pub use data::*;
+pub use future::*;
+pub use loader::*;
+pub use module::*;
+pub use property::*;
 pub use runtime::*;
+#[cfg(feature = "serde")]
+pub use self::serde::*;
+pub use ez_quick_js_derive::{js_methods, JsClass};
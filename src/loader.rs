@@ -0,0 +1,366 @@
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr},
+    fs,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Component, Path, PathBuf},
+    ptr::null_mut,
+};
+
+use crate::{
+    common::{make_cstring, Error},
+    ffi::{
+        js_strdup, JSContext, JSModuleDef, JS_EVAL_FLAG_COMPILE_ONLY, JS_EVAL_TYPE_MODULE,
+        JS_VALUE_GET_PTR,
+    },
+    function::{from_bytecode, js_eval, throw_error},
+    runtime::RuntimeState,
+    Context, JsTag, JsValue,
+};
+
+/// The content of a module returned by [`ModuleLoader::load`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleSource {
+    /// Module source code, compiled as `JS_EVAL_TYPE_MODULE`.
+    Code(String),
+    /// Bytecode of a compiled module, as written by
+    /// [`to_bytecode`](crate::function::to_bytecode).
+    Bytecode(Vec<u8>),
+}
+
+/// Resolves and loads the modules imported by scripts, set with
+/// [`Runtime::set_module_loader`](crate::Runtime::set_module_loader).
+pub trait ModuleLoader {
+    /// Resolve the module `name` imported from the module `base`, the default resolves
+    /// `./` and `../` names against the directory of `base` and keeps other names as is.
+    fn normalize(&self, base: &str, name: &str) -> Result<String, Error> {
+        resolve_module_name(base, name)
+    }
+
+    /// Load the module with the normalized `name`.
+    fn load(&self, name: &str) -> Result<ModuleSource, Error>;
+}
+
+/// Resolve a relative module name like QuickJS' default normalizer, a name going above the
+/// top directory is an error.
+pub fn resolve_module_name(base: &str, name: &str) -> Result<String, Error> {
+    if !name.starts_with('.') {
+        return Ok(name.to_owned());
+    }
+
+    let mut parts: Vec<&str> = base.split('/').collect();
+    // 去掉 base 的文件名，只保留目录
+    parts.pop();
+    for seg in name.split('/') {
+        match seg {
+            "" | "." => {}
+            ".." => match parts.last() {
+                Some(&last) if !last.is_empty() => {
+                    parts.pop();
+                }
+                _ => Err(Error::ValueError(format!(
+                    "Module {name} imported from {base} is outside of the top directory"
+                )))?,
+            },
+            seg => parts.push(seg),
+        }
+    }
+
+    Ok(parts.join("/"))
+}
+
+/// Loads modules from files under a root directory, module names are paths relative to it.
+#[derive(Debug, Clone)]
+pub struct FileLoader {
+    root: PathBuf,
+}
+
+impl FileLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl ModuleLoader for FileLoader {
+    fn load(&self, name: &str) -> Result<ModuleSource, Error> {
+        let path = Path::new(name);
+        // 只允许根目录下的相对路径
+        if !path
+            .components()
+            .all(|comp| matches!(comp, Component::Normal(_) | Component::CurDir))
+        {
+            Err(Error::ValueError(format!(
+                "Module {name} is outside of {}",
+                self.root.display()
+            )))?
+        }
+
+        let code = fs::read_to_string(self.root.join(path))
+            .map_err(|err| Error::GeneralError(format!("Could not load module {name}: {err}")))?;
+
+        Ok(ModuleSource::Code(code))
+    }
+}
+
+/// Loads modules from source code kept in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryLoader {
+    modules: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: &str, code: &str) {
+        self.modules.insert(name.to_owned(), code.to_owned());
+    }
+
+    pub fn with_module(mut self, name: &str, code: &str) -> Self {
+        self.insert(name, code);
+        self
+    }
+}
+
+impl ModuleLoader for MemoryLoader {
+    fn load(&self, name: &str) -> Result<ModuleSource, Error> {
+        match self.modules.get(name) {
+            Some(code) => Ok(ModuleSource::Code(code.clone())),
+            None => Err(Error::GeneralError(format!("Module {name} not found"))),
+        }
+    }
+}
+
+/// Loads precompiled modules from their bytecode.
+#[derive(Debug, Clone, Default)]
+pub struct BytecodeLoader {
+    modules: HashMap<String, Vec<u8>>,
+}
+
+impl BytecodeLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: &str, bytecode: Vec<u8>) {
+        self.modules.insert(name.to_owned(), bytecode);
+    }
+
+    pub fn with_module(mut self, name: &str, bytecode: Vec<u8>) -> Self {
+        self.insert(name, bytecode);
+        self
+    }
+}
+
+impl ModuleLoader for BytecodeLoader {
+    fn load(&self, name: &str) -> Result<ModuleSource, Error> {
+        match self.modules.get(name) {
+            Some(bytecode) => Ok(ModuleSource::Bytecode(bytecode.clone())),
+            None => Err(Error::GeneralError(format!("Module {name} not found"))),
+        }
+    }
+}
+
+/// Compile `source` into the module `name`, the module is owned by the context.
+fn compile_module(
+    ctx: &Context,
+    name: &str,
+    source: ModuleSource,
+) -> Result<*mut JSModuleDef, Error> {
+    let val: JsValue = match source {
+        ModuleSource::Code(code) => js_eval(
+            ctx,
+            &code,
+            name,
+            (JS_EVAL_TYPE_MODULE | JS_EVAL_FLAG_COMPILE_ONLY) as i32,
+        )?,
+        ModuleSource::Bytecode(bytecode) => from_bytecode(ctx, &bytecode)?,
+    };
+    if val.tag() != JsTag::Module {
+        Err(Error::BadType(format!("{name} is not a module")))?
+    }
+
+    Ok(unsafe { JS_VALUE_GET_PTR(*val.raw_value()) } as *mut JSModuleDef)
+}
+
+/// Call the module loader of the runtime, errors and panics are thrown as exceptions.
+unsafe fn with_loader<R>(
+    ctx: *mut JSContext,
+    opaque: *mut c_void,
+    failed: R,
+    f: impl FnOnce(&Context, &dyn ModuleLoader) -> Result<R, Error>,
+) -> R {
+    let state = &*(opaque as *const RuntimeState);
+    Context::with_raw(ctx, |ctx| {
+        let rst = catch_unwind(AssertUnwindSafe(|| {
+            let loader = state.module_loader.borrow();
+            match loader.as_deref() {
+                Some(loader) => f(ctx, loader),
+                None => Err(Error::GeneralError("No module loader".to_owned())),
+            }
+        }))
+        .unwrap_or_else(|_| Err(Error::GeneralError("Module loader panicked".to_owned())));
+
+        rst.unwrap_or_else(|err| {
+            throw_error(ctx, err);
+            failed
+        })
+    })
+}
+
+pub(crate) unsafe extern "C" fn module_normalize(
+    ctx: *mut JSContext,
+    module_base_name: *const c_char,
+    module_name: *const c_char,
+    opaque: *mut c_void,
+) -> *mut c_char {
+    with_loader(ctx, opaque, null_mut(), |ctx, loader| {
+        let base = CStr::from_ptr(module_base_name).to_string_lossy();
+        let name = CStr::from_ptr(module_name).to_string_lossy();
+        let normalized = make_cstring(loader.normalize(&base, &name)?)?;
+
+        // NOTE: QuickJS frees the returned name with js_free().
+        let rst = js_strdup(ctx.inner, normalized.as_ptr());
+        if rst.is_null() {
+            Err(Error::OutOfMemoryError)?
+        }
+
+        Ok(rst)
+    })
+}
+
+pub(crate) unsafe extern "C" fn module_loader(
+    ctx: *mut JSContext,
+    module_name: *const c_char,
+    opaque: *mut c_void,
+) -> *mut JSModuleDef {
+    with_loader(ctx, opaque, null_mut(), |ctx, loader| {
+        let name = CStr::from_ptr(module_name).to_string_lossy();
+        let source = loader.load(&name)?;
+
+        compile_module(ctx, &name, source)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::Error, function::to_bytecode, Context, JsCompiledFunction, Runtime};
+
+    use super::{resolve_module_name, BytecodeLoader, FileLoader, MemoryLoader};
+
+    fn eval_module(ctx: &Context, code: &str) -> Result<String, Error> {
+        let flags = crate::ffi::JS_EVAL_TYPE_MODULE as i32;
+        ctx.eval(code, "main.js", flags)?;
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let rst = ctx.eval("globalThis.result", "<test>", flags)?;
+        crate::function::js_to_string(ctx, &rst)
+    }
+
+    #[test]
+    fn test_resolve_module_name() {
+        assert_eq!(
+            "lib/b.js",
+            resolve_module_name("lib/a.js", "./b.js").unwrap()
+        );
+        assert_eq!("c.js", resolve_module_name("lib/a.js", "../c.js").unwrap());
+        assert_eq!("std", resolve_module_name("lib/a.js", "std").unwrap());
+        assert_eq!(
+            "/x/c.js",
+            resolve_module_name("/x/y/a.js", "../c.js").unwrap()
+        );
+        assert!(resolve_module_name("a.js", "../c.js").is_err());
+    }
+
+    #[test]
+    fn test_memory_loader() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        rt.set_module_loader(
+            MemoryLoader::new()
+                .with_module("lib/math.js", "export const add = (a, b) => a + b;")
+                .with_module(
+                    "lib/util.js",
+                    "import { add } from './math.js'; export const twice = x => add(x, x);",
+                ),
+        );
+
+        let rst = eval_module(
+            ctx,
+            "import { twice } from 'lib/util.js'; globalThis.result = twice(21);",
+        );
+        assert_eq!("42", rst.unwrap());
+
+        let err = ctx
+            .eval(
+                "import 'missing.js';",
+                "main.js",
+                crate::ffi::JS_EVAL_TYPE_MODULE as i32,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Module missing.js not found"));
+
+        // 已加载的模块仍然可用
+        rt.remove_module_loader();
+        let flags = crate::ffi::JS_EVAL_TYPE_MODULE as i32;
+        assert!(ctx.eval("import 'lib/math.js';", "main.js", flags).is_ok());
+        let err = ctx
+            .eval("import 'lib/other.js';", "main.js", flags)
+            .unwrap_err();
+        assert!(matches!(err, Error::JsException(_)));
+    }
+
+    #[test]
+    fn test_file_loader() {
+        let root = std::env::temp_dir().join(format!("ez-quick-js-loader-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+        std::fs::write(root.join("lib/name.js"), "export default 'file';").unwrap();
+
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        rt.set_module_loader(FileLoader::new(&root));
+
+        let rst = eval_module(
+            ctx,
+            "import name from './lib/name.js'; globalThis.result = name;",
+        );
+        assert_eq!("file", rst.unwrap());
+
+        let err = eval_module(ctx, "import '/etc/passwd';").unwrap_err();
+        assert!(err.to_string().contains("is outside of"));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_bytecode_loader() {
+        let bytecode = {
+            let rt = Runtime::default();
+            let ctx = &Context::new(&rt);
+            let flags =
+                (crate::ffi::JS_EVAL_TYPE_MODULE | crate::ffi::JS_EVAL_FLAG_COMPILE_ONLY) as i32;
+            let module = ctx
+                .eval("export const value = 'bytecode';", "value.js", flags)
+                .unwrap();
+            let module = JsCompiledFunction {
+                ctx,
+                inner: unsafe { module.forget() },
+            };
+            to_bytecode(ctx, &module)
+        };
+
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        rt.set_module_loader(BytecodeLoader::new().with_module("value.js", bytecode));
+
+        let rst = eval_module(
+            ctx,
+            "import { value } from 'value.js'; globalThis.result = value;",
+        );
+        assert_eq!("bytecode", rst.unwrap());
+    }
+}
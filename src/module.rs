@@ -0,0 +1,227 @@
+use std::{
+    ffi::{c_int, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use crate::{
+    common::{make_cstring, Error},
+    ffi::{JSContext, JSModuleDef, JS_SetModuleExport},
+    function::{add_module_export, new_c_module, new_function, throw_error},
+    new_native_class, Args, Context, IntoJs, JsModuleDef, JsValue, NativeMethods,
+};
+
+/// Creates the value of an export when the module is initialized.
+pub(crate) type ExportInit = Box<dyn for<'c> FnOnce(&'c Context<'c>) -> Result<JsValue<'c>, Error>>;
+
+/// Builder of a native ES module, created with [`Context::module`].
+///
+/// ```ignore
+/// ctx.module("m")
+///     .function("add", |ctx, _this, args| (args.get::<i32>(0)? + args.get::<i32>(1)?).into_js(ctx))
+///     .value("VERSION", "1.0")
+///     .class::<Point>()
+///     .build()?;
+/// ```
+pub struct ModuleBuilder<'a> {
+    ctx: &'a Context<'a>,
+    name: String,
+    exports: Vec<(String, ExportInit)>,
+}
+
+impl<'a> ModuleBuilder<'a> {
+    pub fn new(ctx: &'a Context<'a>, name: &str) -> Self {
+        Self {
+            ctx,
+            name: name.to_owned(),
+            exports: Vec::new(),
+        }
+    }
+
+    /// Export a function calling `func`.
+    pub fn function<F>(mut self, name: &str, func: F) -> Self
+    where
+        F: for<'c> Fn(&'c Context<'c>, JsValue<'c>, Args<'c>) -> Result<JsValue<'c>, Error>
+            + 'static,
+    {
+        let fn_name = name.to_owned();
+        self.exports.push((
+            name.to_owned(),
+            Box::new(move |ctx| new_function(ctx, &fn_name, func)),
+        ));
+        self
+    }
+
+    /// Export `value`, converted when the module is initialized.
+    pub fn value<V>(mut self, name: &str, value: V) -> Self
+    where
+        V: for<'c> IntoJs<'c> + 'static,
+    {
+        self.exports
+            .push((name.to_owned(), Box::new(move |ctx| value.into_js(ctx))));
+        self
+    }
+
+    /// Export the constructor of the native class `T` as `T::NAME`.
+    pub fn class<T: NativeMethods>(mut self) -> Self {
+        self.exports.push((
+            T::NAME.to_owned(),
+            Box::new(|ctx| new_native_class::<T>(ctx)),
+        ));
+        self
+    }
+
+    /// Create the module, the exported values are created when it is first imported.
+    pub fn build(self) -> Result<JsModuleDef<'a>, Error> {
+        let mut exports: Vec<(CString, ExportInit)> = Vec::with_capacity(self.exports.len());
+        for (name, init) in self.exports {
+            let c_name = make_cstring(name.as_str())?;
+            if exports.iter().any(|(other, _)| *other == c_name) {
+                Err(Error::ValueError(format!(
+                    "Duplicated export {name} in module {}",
+                    self.name
+                )))?
+            }
+            exports.push((c_name, init));
+        }
+
+        let m = new_c_module(self.ctx, &self.name, Some(module_init))?;
+        for (name, _) in &exports {
+            add_module_export(self.ctx, &m, name.as_ptr())?;
+        }
+        self.ctx
+            .get_runtime()
+            .state()
+            .native_modules
+            .borrow_mut()
+            .insert(m.raw_value(), exports);
+
+        Ok(m)
+    }
+}
+
+unsafe extern "C" fn module_init(ctx: *mut JSContext, m: *mut JSModuleDef) -> c_int {
+    Context::with_raw(ctx, |ctx| {
+        let exports = ctx
+            .get_runtime()
+            .state()
+            .native_modules
+            .borrow_mut()
+            .remove(&m)
+            .unwrap_or_default();
+
+        let rst = catch_unwind(AssertUnwindSafe(|| {
+            for (name, init) in exports {
+                let val = init(ctx)?;
+                // NOTE: JS_SetModuleExport takes ownership of the value.
+                if JS_SetModuleExport(ctx.inner, m, name.as_ptr(), val.forget()) != 0 {
+                    Err(Error::GeneralError(format!(
+                        "Could not set export {}",
+                        name.to_string_lossy()
+                    )))?
+                }
+            }
+
+            Ok(())
+        }))
+        .unwrap_or_else(|_| Err(Error::GeneralError("Module init panicked".to_owned())));
+
+        match rst {
+            Ok(()) => 0,
+            Err(err) => {
+                throw_error(ctx, err);
+                -1
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        common::Error, function::js_to_string, with_native, Args, Context, IntoJs, JsValue,
+        NativeClass, NativeMethods, Runtime,
+    };
+
+    struct Point {
+        x: i32,
+    }
+
+    impl NativeClass for Point {
+        const NAME: &'static str = "Point";
+    }
+
+    impl NativeMethods for Point {
+        fn constructor(_ctx: &Context, args: Args) -> Result<Self, Error> {
+            Ok(Self { x: args.get(0)? })
+        }
+
+        fn define_methods(ctx: &Context, proto: &JsValue) -> Result<(), Error> {
+            crate::define_method(ctx, proto, "getX", |ctx, this, _args| {
+                with_native(&this, |point: &Point| point.x)?.into_js(ctx)
+            })
+        }
+    }
+
+    #[test]
+    fn test_module_builder() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+
+        ctx.module("m")
+            .function("add", |ctx, _this, args| {
+                (args.get::<i32>(0)? + args.get::<i32>(1)?).into_js(ctx)
+            })
+            .value("VERSION", "1.0")
+            .class::<Point>()
+            .build()
+            .unwrap();
+
+        let code = r#"
+            import { add, VERSION, Point } from 'm';
+            globalThis.result = [add(1, 2), VERSION, new Point(7).getX()].join("|");
+        "#;
+        ctx.eval(code, "main.js", crate::ffi::JS_EVAL_TYPE_MODULE as i32)
+            .unwrap();
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+        let rst = ctx.eval("globalThis.result", "<test>", flags).unwrap();
+        assert_eq!("3|1.0|7", js_to_string(ctx, &rst).unwrap());
+
+        let dup = ctx.module("dup").value("a", 1).value("a", 2).build();
+        assert!(matches!(dup, Err(Error::ValueError(_))));
+    }
+
+    struct Unsupported;
+
+    impl<'a> IntoJs<'a> for Unsupported {
+        fn into_js(self, _ctx: &'a Context) -> Result<JsValue<'a>, Error> {
+            Err(Error::ValueError("unsupported".to_owned()))
+        }
+    }
+
+    #[test]
+    fn test_module_init_error() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+
+        ctx.module("bad")
+            .function("ok", |ctx, _this, _args| ().into_js(ctx))
+            .value("name", Unsupported)
+            .build()
+            .unwrap();
+
+        // 模块初始化在求值阶段进行，错误会使返回的 promise 被拒绝
+        let flags = crate::ffi::JS_EVAL_TYPE_MODULE as i32;
+        let promise = ctx
+            .eval("import { name } from 'bad';", "main.js", flags)
+            .unwrap();
+        let state = unsafe { crate::ffi::JS_PromiseState(ctx.inner, promise.inner) };
+        assert_eq!(crate::ffi::JSPromiseStateEnum_JS_PROMISE_REJECTED, state);
+        let reason = JsValue::new(ctx, unsafe {
+            crate::ffi::JS_PromiseResult(ctx.inner, promise.inner)
+        });
+        assert_eq!(
+            "Error: Value error: unsupported",
+            js_to_string(ctx, &reason).unwrap()
+        );
+    }
+}
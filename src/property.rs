@@ -0,0 +1,147 @@
+use crate::{
+    common::Error,
+    ffi::{JS_NewAtomUInt32, JS_ValueToAtom, JS_ATOM_NULL},
+    function::get_last_exception,
+    Context, JsAtom, JsSymbol,
+};
+
+/// Key of a property, array indices are used without conversion to an atom.
+#[derive(Debug, Clone)]
+pub enum PropertyKey<'a> {
+    Index(u32),
+    Atom(JsAtom<'a>),
+}
+
+impl<'a> PropertyKey<'a> {
+    /// Convert the key into an atom, indices are interned.
+    pub fn to_atom(&self, ctx: &'a Context) -> Result<JsAtom<'a>, Error> {
+        match self {
+            PropertyKey::Index(idx) => {
+                let atom = JsAtom::new(ctx, unsafe { JS_NewAtomUInt32(ctx.inner, *idx) });
+                check_atom(ctx, atom, "Could not create index atom")
+            }
+            PropertyKey::Atom(atom) => Ok(atom.clone()),
+        }
+    }
+}
+
+fn check_atom<'a>(ctx: &'a Context, atom: JsAtom<'a>, err_msg: &str) -> Result<JsAtom<'a>, Error> {
+    if atom.is_exception() {
+        Err(get_last_exception(ctx).unwrap_or_else(|| Error::PropertyError(err_msg.to_owned())))?
+    }
+
+    Ok(atom)
+}
+
+/// Conversion into a [`PropertyKey`], implemented for `u32` indices, strings, atoms and
+/// symbols.
+pub trait IntoPropertyKey<'a> {
+    fn into_key(self, ctx: &'a Context) -> Result<PropertyKey<'a>, Error>;
+}
+
+impl<'a> IntoPropertyKey<'a> for PropertyKey<'a> {
+    fn into_key(self, _ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        Ok(self)
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for u32 {
+    fn into_key(self, _ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        Ok(PropertyKey::Index(self))
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for &str {
+    fn into_key(self, ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        Ok(PropertyKey::Atom(ctx.new_atom(self)?))
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for &String {
+    fn into_key(self, ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        self.as_str().into_key(ctx)
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for String {
+    fn into_key(self, ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        self.as_str().into_key(ctx)
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for JsAtom<'a> {
+    fn into_key(self, _ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        Ok(PropertyKey::Atom(self))
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for &JsAtom<'a> {
+    fn into_key(self, _ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        Ok(PropertyKey::Atom(self.clone()))
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for &JsSymbol<'a> {
+    fn into_key(self, ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        let atom = JsAtom::new(ctx, unsafe { JS_ValueToAtom(ctx.inner, self.inner) });
+        let atom = check_atom(ctx, atom, "Could not convert symbol to atom")?;
+
+        Ok(PropertyKey::Atom(atom))
+    }
+}
+
+impl<'a> IntoPropertyKey<'a> for JsSymbol<'a> {
+    fn into_key(self, ctx: &'a Context) -> Result<PropertyKey<'a>, Error> {
+        (&self).into_key(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Context, JsSymbol, PropertyKey, Runtime};
+
+    #[test]
+    fn test_property_key() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let arr = ctx.eval("[1, 2, 3]", "<test>", flags).unwrap();
+        assert_eq!(2, arr.get_property(1).unwrap().to_int().unwrap().value());
+        arr.set_property(5, ctx.get_int(6)).unwrap();
+        let len = arr.get_property("length").unwrap();
+        assert_eq!(6, len.to_int().unwrap().value());
+
+        let key = PropertyKey::Index(0).to_atom(ctx).unwrap();
+        assert_eq!("0", key.to_str());
+        assert_eq!(1, arr.get_property(&key).unwrap().to_int().unwrap().value());
+
+        let name = ctx.new_atom("name").unwrap();
+        let obj = ctx.new_object().unwrap();
+        obj.set_property(&name, ctx.get_string("x")).unwrap();
+        assert_eq!(
+            "x",
+            obj.get_property("name")
+                .unwrap()
+                .to_string()
+                .unwrap()
+                .value()
+        );
+
+        // Symbol 键的属性
+        let iterator: JsSymbol = ctx
+            .eval("Symbol.iterator", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(arr.get_property(&iterator).unwrap().is_function());
+        obj.define_property(&iterator, ctx.get_int(1), 0).unwrap();
+        ctx.get_global_object().set_property("obj", obj).unwrap();
+        let rst = ctx.eval("obj[Symbol.iterator]", "<test>", flags).unwrap();
+        assert_eq!(1, rst.to_int().unwrap().value());
+        let rst = ctx
+            .eval("Object.getOwnPropertySymbols(obj).length", "<test>", flags)
+            .unwrap();
+        assert_eq!(1, rst.to_int().unwrap().value());
+    }
+}
@@ -1,35 +1,579 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::{c_int, c_void, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::null_mut,
+    sync::{Arc, Mutex},
+    task::{self, Poll},
+    time::{Duration, Instant},
+};
+
 use crate::{
-    ffi::{JSRuntime, JS_FreeRuntime, JS_NewRuntime, JS_SetMemoryLimit},
-    Context,
+    common::{make_cstring, Error, ExceptionValue},
+    ffi::{
+        JSContext, JSModuleDef, JSRuntime, JSValue, JS_DupValue, JS_ExecutePendingJob,
+        JS_FreeRuntime, JS_FreeValueRT, JS_GetRuntimeOpaque, JS_IsJobPending, JS_NewRuntime,
+        JS_SetCanBlock, JS_SetGCThreshold, JS_SetHostPromiseRejectionTracker,
+        JS_SetInterruptHandler, JS_SetMaxStackSize, JS_SetMemoryLimit, JS_SetModuleLoaderFunc,
+        JS_SetRuntimeInfo, JS_SetRuntimeOpaque,
+    },
+    function::{get_last_exception, new_exception},
+    future::{Idle, Task},
+    loader::{module_loader, module_normalize},
+    module::ExportInit,
+    ClassRegistry, Context, JsClass, JsPromise, JsValue, ModuleBuilder, ModuleLoader,
 };
 
+/// Runtime options, validated by [`RuntimeConfig::validate`] before the runtime is created.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    /// Memory limit in bytes (`JS_SetMemoryLimit`).
+    pub memory_limit: Option<usize>,
+    /// Maximum stack size in bytes, `0` disables the stack check (`JS_SetMaxStackSize`).
+    pub max_stack_size: Option<usize>,
+    /// Allocated bytes that trigger a GC cycle (`JS_SetGCThreshold`).
+    pub gc_threshold: Option<usize>,
+    /// Whether `Atomics.wait` may block (`JS_SetCanBlock`).
+    pub can_block: Option<bool>,
+    /// Runtime information string, shown in memory usage dumps (`JS_SetRuntimeInfo`).
+    pub info: Option<String>,
+}
+
+impl RuntimeConfig {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.memory_limit == Some(0) {
+            Err(Error::ValueError(
+                "memory_limit must be greater than 0".to_owned(),
+            ))?
+        }
+
+        if self.gc_threshold == Some(0) {
+            Err(Error::ValueError(
+                "gc_threshold must be greater than 0".to_owned(),
+            ))?
+        }
+
+        if let (Some(limit), Some(threshold)) = (self.memory_limit, self.gc_threshold) {
+            if threshold > limit {
+                Err(Error::ValueError(format!(
+                    "gc_threshold ({threshold}) must not exceed memory_limit ({limit})"
+                )))?
+            }
+        }
+
+        if let Some(info) = &self.info {
+            if info.contains('\0') {
+                Err(Error::ValueError(
+                    "info must not contain NUL bytes".to_owned(),
+                ))?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder of [`Runtime`].
+///
+/// ```ignore
+/// let rt = RuntimeBuilder::new()
+///     .memory_limit(64 * 1024 * 1024)
+///     .max_stack_size(1024 * 1024)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeBuilder {
+    config: RuntimeConfig,
+}
+
+impl RuntimeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: RuntimeConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn memory_limit(mut self, limit: usize) -> Self {
+        self.config.memory_limit = Some(limit);
+        self
+    }
+
+    pub fn max_stack_size(mut self, stack_size: usize) -> Self {
+        self.config.max_stack_size = Some(stack_size);
+        self
+    }
+
+    pub fn gc_threshold(mut self, gc_threshold: usize) -> Self {
+        self.config.gc_threshold = Some(gc_threshold);
+        self
+    }
+
+    pub fn can_block(mut self, can_block: bool) -> Self {
+        self.config.can_block = Some(can_block);
+        self
+    }
+
+    pub fn info(mut self, info: &str) -> Self {
+        self.config.info = Some(info.to_owned());
+        self
+    }
+
+    pub fn config(&self) -> &RuntimeConfig {
+        &self.config
+    }
+
+    /// Validate the options and create the runtime.
+    pub fn build(self) -> Result<Runtime, Error> {
+        let config = self.config;
+        config.validate()?;
+
+        // JS_SetRuntimeInfo() keeps the pointer, so the string is owned by the runtime state.
+        let info = config.info.as_deref().map(make_cstring).transpose()?;
+
+        let inner = unsafe { JS_NewRuntime() };
+        if inner.is_null() {
+            Err(Error::GeneralError("JS_NewRuntime() failed".to_owned()))?
+        }
+
+        unsafe {
+            if let Some(limit) = config.memory_limit {
+                JS_SetMemoryLimit(inner, limit);
+            }
+            if let Some(stack_size) = config.max_stack_size {
+                JS_SetMaxStackSize(inner, stack_size);
+            }
+            if let Some(threshold) = config.gc_threshold {
+                JS_SetGCThreshold(inner, threshold);
+            }
+            if let Some(can_block) = config.can_block {
+                JS_SetCanBlock(inner, can_block as i32);
+            }
+            if let Some(info) = &info {
+                JS_SetRuntimeInfo(inner, info.as_ptr());
+            }
+        }
+
+        let runtime = unsafe { Runtime::from_raw(inner) };
+        runtime.state().info.replace(info);
+
+        Ok(runtime)
+    }
+}
+
+/// QuickJS polls the interrupt handler once every `JS_INTERRUPT_COUNTER_INIT` branches or calls.
+const INSTRUCTIONS_PER_INTERRUPT_CHECK: u64 = 10000;
+
+/// Limit on how long a script may run before it is interrupted with [`Error::Interrupted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionLimit {
+    /// Wall-clock time limit.
+    Timeout(Duration),
+    /// Approximate instruction budget. QuickJS checks for interruption once every 10000
+    /// branches or calls, so the budget is rounded up to a multiple of 10000 and a budget
+    /// under 10000 still lets the script run until the first check.
+    Instructions(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LimitState {
+    Deadline(Instant),
+    Checks(u64),
+}
+
+impl LimitState {
+    fn new(limit: ExecutionLimit) -> Self {
+        match limit {
+            ExecutionLimit::Timeout(timeout) => LimitState::Deadline(Instant::now() + timeout),
+            // 第 n 次检查时已执行约 n * 10000 条指令，在预算用完后的第一次检查中断
+            ExecutionLimit::Instructions(count) => LimitState::Checks(
+                count
+                    .div_ceil(INSTRUCTIONS_PER_INTERRUPT_CHECK)
+                    .saturating_sub(1),
+            ),
+        }
+    }
+}
+
+type InterruptHandler = Box<dyn FnMut() -> bool>;
+
+type RejectionHandler = Box<dyn for<'c> FnMut(&'c Context<'c>, JsPromise<'c>, JsValue<'c>, bool)>;
+
+/// Rust side data of a runtime, stored as the `JSRuntime` opaque.
+#[derive(Default)]
+pub(crate) struct RuntimeState {
+    pub(crate) info: RefCell<Option<CString>>,
+    interrupt_handler: RefCell<Option<InterruptHandler>>,
+    execution_limit: Cell<Option<LimitState>>,
+    /// Thrown values of the captured exceptions, by key.
+    exception_values: RefCell<HashMap<u64, JSValue>>,
+    /// Keys of the values whose exceptions were dropped, see [`ExceptionValue`].
+    released_exceptions: Arc<Mutex<Vec<u64>>>,
+    exception_seq: Cell<u64>,
+    classes: ClassRegistry,
+    pub(crate) module_loader: RefCell<Option<Box<dyn ModuleLoader>>>,
+    /// Exports of the modules created by [`ModuleBuilder`], until they are initialized.
+    pub(crate) native_modules: RefCell<HashMap<*mut JSModuleDef, Vec<(CString, ExportInit)>>>,
+    /// Futures spawned by [`Context::new_promise_from_future`].
+    pub(crate) tasks: RefCell<Vec<Task>>,
+    rejection_handler: RefCell<Option<RejectionHandler>>,
+    /// Rejections without handler collected while no rejection handler is set, the promise
+    /// is kept to recognize it when a handler is attached later.
+    unhandled_rejections: RefCell<Vec<(JSValue, Error)>>,
+}
+
+impl RuntimeState {
+    /// Keep `value` (already duplicated) for an exception, until the returned handle is
+    /// dropped.
+    pub(crate) fn store_exception(&self, rt: *mut JSRuntime, value: JSValue) -> ExceptionValue {
+        self.free_released_exceptions(rt);
+
+        let id = self.exception_seq.get() + 1;
+        self.exception_seq.set(id);
+        self.exception_values.borrow_mut().insert(id, value);
+
+        ExceptionValue {
+            id,
+            released: self.released_exceptions.clone(),
+        }
+    }
+
+    /// The value kept for `handle`, `None` if the handle comes from another runtime.
+    pub(crate) fn exception_value(&self, handle: &ExceptionValue) -> Option<JSValue> {
+        if !Arc::ptr_eq(&handle.released, &self.released_exceptions) {
+            return None;
+        }
+
+        self.exception_values.borrow().get(&handle.id).copied()
+    }
+
+    fn free_released_exceptions(&self, rt: *mut JSRuntime) {
+        let released = std::mem::take(
+            &mut *self
+                .released_exceptions
+                .lock()
+                .unwrap_or_else(|err| err.into_inner()),
+        );
+        let mut values = self.exception_values.borrow_mut();
+        for id in released {
+            if let Some(value) = values.remove(&id) {
+                unsafe { JS_FreeValueRT(rt, value) };
+            }
+        }
+    }
+
+    fn track_rejection(&self, ctx: &Context, promise: JSValue, reason: JSValue, is_handled: bool) {
+        // 处理函数内再次触发时跳过
+        let Ok(mut handler) = self.rejection_handler.try_borrow_mut() else {
+            return;
+        };
+        let dup = |val| unsafe {
+            JS_DupValue(ctx.inner, val);
+            JsValue::new(ctx, val)
+        };
+
+        if let Some(handler) = handler.as_mut() {
+            if let Ok(promise) = dup(promise).try_into() {
+                handler(ctx, promise, dup(reason), is_handled);
+            }
+        } else if is_handled {
+            self.forget_rejection(ctx.get_runtime().inner, promise);
+        } else {
+            // 异常各自持有抛出的值，不影响其他已捕获的异常
+            let err = Error::JsException(Box::new(new_exception(ctx, &dup(reason))));
+            let promise = unsafe { dup(promise).forget() };
+            self.unhandled_rejections.borrow_mut().push((promise, err));
+        }
+    }
+
+    /// Remove `promise` from the collected unhandled rejections.
+    pub(crate) fn forget_rejection(&self, rt: *mut JSRuntime, promise: JSValue) {
+        self.unhandled_rejections.borrow_mut().retain(|(val, _)| {
+            let same = unsafe { val.u.ptr == promise.u.ptr };
+            if same {
+                unsafe { JS_FreeValueRT(rt, *val) };
+            }
+            !same
+        });
+    }
+
+    fn should_interrupt(&self) -> bool {
+        let over_limit = match self.execution_limit.get() {
+            Some(LimitState::Deadline(deadline)) => Instant::now() >= deadline,
+            Some(LimitState::Checks(0)) => true,
+            Some(LimitState::Checks(n)) => {
+                self.execution_limit.set(Some(LimitState::Checks(n - 1)));
+                false
+            }
+            None => false,
+        };
+
+        over_limit
+            || match self.interrupt_handler.try_borrow_mut() {
+                Ok(mut handler) => handler.as_mut().map(|f| f()).unwrap_or(false),
+                Err(_) => false,
+            }
+    }
+}
+
+unsafe extern "C" fn interrupt_handler(_rt: *mut JSRuntime, opaque: *mut c_void) -> c_int {
+    let state = &*(opaque as *const RuntimeState);
+    // A panicking handler stops the script instead of unwinding into C.
+    let interrupted = catch_unwind(AssertUnwindSafe(|| state.should_interrupt())).unwrap_or(true);
+
+    interrupted as c_int
+}
+
+unsafe extern "C" fn promise_rejection_tracker(
+    ctx: *mut JSContext,
+    promise: JSValue,
+    reason: JSValue,
+    is_handled: c_int,
+    opaque: *mut c_void,
+) {
+    let state = &*(opaque as *const RuntimeState);
+    Context::with_raw(ctx, |ctx| {
+        // A panicking handler must not unwind into C.
+        let _ = catch_unwind(AssertUnwindSafe(|| {
+            state.track_rejection(ctx, promise, reason, is_handled != 0)
+        }));
+    })
+}
+
 pub struct Runtime {
     pub(crate) inner: *mut JSRuntime,
 }
 
 impl Runtime {
     pub fn new(memory_limit: Option<usize>) -> Self {
-        let inner = unsafe { JS_NewRuntime() };
-        if inner.is_null() {
-            panic!("Runtime create failed");
-        }
+        let mut builder = RuntimeBuilder::new();
         // Configure memory limit if specified.
         if let Some(limit) = memory_limit {
-            unsafe {
-                JS_SetMemoryLimit(inner, limit);
-            }
+            builder = builder.memory_limit(limit);
         }
 
-        Self { inner }
+        builder.build().expect("Runtime create failed")
     }
 
-    pub fn from_raw(js_runtime: *mut JSRuntime) -> Self {
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::new()
+    }
+
+    /// Take ownership of a raw runtime, the runtime is freed when the returned value is dropped.
+    ///
+    /// # Safety
+    /// `js_runtime` must be a valid runtime whose opaque is either null or was set by this crate.
+    pub unsafe fn from_raw(js_runtime: *mut JSRuntime) -> Self {
+        if JS_GetRuntimeOpaque(js_runtime).is_null() {
+            let state = Box::into_raw(Box::<RuntimeState>::default()) as *mut c_void;
+            JS_SetRuntimeOpaque(js_runtime, state);
+            JS_SetInterruptHandler(js_runtime, Some(interrupt_handler), state);
+            JS_SetHostPromiseRejectionTracker(js_runtime, Some(promise_rejection_tracker), state);
+        }
+
         Self { inner: js_runtime }
     }
 
     pub fn create_context<'a>(&'a self) -> Context<'a> {
         Context::new(self)
     }
+
+    /// Set a handler polled during script execution, returning `true` interrupts the
+    /// running script with [`Error::Interrupted`].
+    pub fn set_interrupt_handler<F>(&self, handler: F)
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        self.state()
+            .interrupt_handler
+            .replace(Some(Box::new(handler)));
+    }
+
+    pub fn remove_interrupt_handler(&self) {
+        self.state().interrupt_handler.replace(None);
+    }
+
+    /// Set the loader resolving the modules imported by scripts, modules created with
+    /// [`Context::new_module`] are still found without calling it.
+    pub fn set_module_loader<L>(&self, loader: L)
+    where
+        L: ModuleLoader + 'static,
+    {
+        let state = self.state();
+        state.module_loader.replace(Some(Box::new(loader)));
+        unsafe {
+            JS_SetModuleLoaderFunc(
+                self.inner,
+                Some(module_normalize),
+                Some(module_loader),
+                state as *const RuntimeState as *mut c_void,
+            )
+        };
+    }
+
+    pub fn remove_module_loader(&self) {
+        unsafe { JS_SetModuleLoaderFunc(self.inner, None, None, std::ptr::null_mut()) };
+        self.state().module_loader.replace(None);
+    }
+
+    /// Run `f` with an execution limit, scripts still running when the limit is reached
+    /// fail with [`Error::Interrupted`].
+    pub fn with_execution_limit<R>(&self, limit: ExecutionLimit, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous limit, also when `f` panics.
+        struct Restore<'s>(&'s Cell<Option<LimitState>>, Option<LimitState>);
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.1);
+            }
+        }
+
+        let limit_state = &self.state().execution_limit;
+        let _restore = Restore(
+            limit_state,
+            limit_state.replace(Some(LimitState::new(limit))),
+        );
+
+        f()
+    }
+
+    /// The classes registered in this runtime.
+    pub fn class_registry(&self) -> &ClassRegistry {
+        &self.state().classes
+    }
+
+    /// Register a class named `name` whose instances own a `T`, registering the same type
+    /// again returns the existing class.
+    pub fn register_class<T: 'static>(&self, name: &str) -> Result<JsClass<T>, Error> {
+        self.state().classes.register::<T>(self.inner, name)
+    }
+
+    pub fn is_job_pending(&self) -> bool {
+        unsafe { JS_IsJobPending(self.inner) != 0 }
+    }
+
+    /// Run one pending job (promise reactions, ...), returns `false` if no job was pending.
+    pub fn execute_pending_job(&self) -> Result<bool, Error> {
+        let mut pctx = null_mut();
+        let rst = unsafe { JS_ExecutePendingJob(self.inner, &mut pctx) };
+        if rst < 0 {
+            let err = unsafe { Context::with_raw(pctx, get_last_exception) };
+            Err(err.unwrap_or_else(|| Error::ExecuteError("Pending job failed".to_owned())))?
+        }
+
+        Ok(rst > 0)
+    }
+
+    /// Run pending jobs until the queue is empty and return how many were run, stopping at
+    /// the first job which throws.
+    ///
+    /// Without rejection handler, the promises rejected with no handler once the queue is
+    /// empty are all reported at once: a single rejection as a [`Error::JsException`] of its
+    /// reason, several as [`Error::UnhandledRejections`] in the order they happened.
+    pub fn run_jobs_until_idle(&self) -> Result<usize, Error> {
+        let count = self.run_jobs()?;
+        self.report_unhandled_rejections()?;
+
+        Ok(count)
+    }
+
+    /// Run pending jobs until the queue is empty, the unhandled rejections stay collected.
+    fn run_jobs(&self) -> Result<usize, Error> {
+        let mut count = 0;
+        while self.execute_pending_job()? {
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Take the collected unhandled rejections as an error, see
+    /// [`Runtime::run_jobs_until_idle`].
+    fn report_unhandled_rejections(&self) -> Result<(), Error> {
+        let mut errors = self.take_unhandled_rejections();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(Error::UnhandledRejections(errors)),
+        }
+    }
+
+    /// Call `handler` with `(ctx, promise, reason, is_handled)` when a promise is rejected
+    /// without handler (`is_handled` is `false`), and when a handler is attached to such a
+    /// promise later (`is_handled` is `true`).
+    pub fn on_unhandled_rejection<F>(&self, handler: F)
+    where
+        F: for<'c> FnMut(&'c Context<'c>, JsPromise<'c>, JsValue<'c>, bool) + 'static,
+    {
+        self.state()
+            .rejection_handler
+            .replace(Some(Box::new(handler)));
+    }
+
+    /// Remove the rejection handler, unhandled rejections are collected again.
+    pub fn remove_unhandled_rejection_handler(&self) {
+        self.state().rejection_handler.replace(None);
+    }
+
+    /// Take the collected unhandled rejections, as [`Error::JsException`] of their reasons.
+    pub fn take_unhandled_rejections(&self) -> Vec<Error> {
+        let rejections = self.state().unhandled_rejections.take();
+        rejections
+            .into_iter()
+            .map(|(promise, err)| {
+                unsafe { JS_FreeValueRT(self.inner, promise) };
+                err
+            })
+            .collect()
+    }
+
+    /// Drive the runtime: run the pending jobs and poll the spawned futures until neither
+    /// can make progress. Returns `Poll::Ready` once no job and no future is left, the spawned
+    /// futures wake `cx` when they can make progress again. The unhandled rejections are
+    /// then reported like [`Runtime::run_jobs_until_idle`] does.
+    pub fn poll_idle(&self, cx: &mut task::Context<'_>) -> Poll<Result<(), Error>> {
+        match self.poll_progress(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(self.report_unhandled_rejections()),
+            poll => poll,
+        }
+    }
+
+    /// [`Runtime::poll_idle`] without reporting the unhandled rejections, they stay collected
+    /// for the caller interested in them.
+    pub(crate) fn poll_progress(&self, cx: &mut task::Context<'_>) -> Poll<Result<(), Error>> {
+        let state = self.state();
+        loop {
+            self.run_jobs()?;
+
+            // 轮询期间 future 可能再创建新的 future (或再次驱动 runtime)，所以先取出任务列表
+            let mut tasks = state.tasks.take();
+            tasks.retain_mut(|task| task.as_mut().poll(cx).is_pending());
+
+            let mut pending = state.tasks.borrow_mut();
+            let spawned = !pending.is_empty();
+            tasks.append(&mut pending);
+            *pending = tasks;
+
+            if !spawned && !self.is_job_pending() {
+                return if pending.is_empty() {
+                    Poll::Ready(Ok(()))
+                } else {
+                    Poll::Pending
+                };
+            }
+        }
+    }
+
+    /// A future completing when all pending jobs have run and all spawned futures are done.
+    pub fn idle(&self) -> Idle<'_> {
+        Idle { rt: self }
+    }
+
+    pub(crate) fn state(&self) -> &RuntimeState {
+        unsafe { &*(JS_GetRuntimeOpaque(self.inner) as *const RuntimeState) }
+    }
 }
 
 impl Default for Runtime {
@@ -40,6 +584,309 @@ impl Default for Runtime {
 
 impl Drop for Runtime {
     fn drop(&mut self) {
-        unsafe { JS_FreeRuntime(self.inner) }
+        unsafe {
+            let state = JS_GetRuntimeOpaque(self.inner) as *mut RuntimeState;
+            if !state.is_null() {
+                // 状态持有的 JS 值必须在 runtime 释放前释放
+                for (_, value) in (*state).exception_values.take() {
+                    JS_FreeValueRT(self.inner, value);
+                }
+                // 未完成的 future 持有 promise 的 resolve 函数，需在 runtime 释放前丢弃
+                drop((*state).tasks.take());
+                for (promise, _) in (*state).unhandled_rejections.take() {
+                    JS_FreeValueRT(self.inner, promise);
+                }
+            }
+            // 状态在 runtime 释放之后才销毁，释放期间运行的 finalizer 仍能访问状态
+            JS_FreeRuntime(self.inner);
+
+            if !state.is_null() {
+                drop(Box::from_raw(state));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::JS_EVAL_TYPE_GLOBAL;
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let rt = RuntimeBuilder::new()
+            .memory_limit(32 * 1024 * 1024)
+            .max_stack_size(512 * 1024)
+            .gc_threshold(1024 * 1024)
+            .can_block(false)
+            .info("test runtime")
+            .build()
+            .unwrap();
+        let ctx = &rt.create_context();
+
+        let rst = ctx
+            .eval("1 + 2", "<test>", JS_EVAL_TYPE_GLOBAL as i32)
+            .unwrap();
+        assert_eq!(3, rst.to_int().unwrap().value());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(RuntimeBuilder::new().memory_limit(0).build().is_err());
+        assert!(RuntimeBuilder::new().gc_threshold(0).build().is_err());
+        assert!(RuntimeBuilder::new()
+            .memory_limit(1024)
+            .gc_threshold(2048)
+            .build()
+            .is_err());
+        assert!(RuntimeBuilder::new().info("a\0b").build().is_err());
+    }
+
+    #[test]
+    fn test_interrupt_handler() {
+        let rt = Runtime::default();
+        let ctx = &rt.create_context();
+
+        let calls = std::rc::Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        rt.set_interrupt_handler(move || {
+            counter.set(counter.get() + 1);
+            counter.get() > 3
+        });
+
+        let rst = ctx.eval("while (true) {}", "<test>", JS_EVAL_TYPE_GLOBAL as i32);
+        assert!(matches!(rst, Err(Error::Interrupted)));
+        assert_eq!(4, calls.get());
+
+        calls.set(0);
+        let rst = ctx.eval(
+            "try { while (true) {} } catch (e) {} 1",
+            "<test>",
+            JS_EVAL_TYPE_GLOBAL as i32,
+        );
+        assert!(matches!(rst, Err(Error::Interrupted)));
+
+        rt.remove_interrupt_handler();
+        let rst = ctx
+            .eval("1 + 1", "<test>", JS_EVAL_TYPE_GLOBAL as i32)
+            .unwrap();
+        assert_eq!(2, rst.to_int().unwrap().value());
+    }
+
+    #[test]
+    fn test_execution_limit() {
+        let rt = Runtime::default();
+        let ctx = &rt.create_context();
+
+        let rst = ctx.eval_with_timeout("for (;;) {}", Duration::from_millis(50));
+        assert!(matches!(rst, Err(Error::Interrupted)));
+
+        let rst = ctx.eval_with_limit(
+            "while (true) {}",
+            "<test>",
+            JS_EVAL_TYPE_GLOBAL as i32,
+            ExecutionLimit::Instructions(100_000),
+        );
+        assert!(matches!(rst, Err(Error::Interrupted)));
+
+        let rst = ctx
+            .eval_with_timeout(
+                "let n = 0; for (let i = 0; i < 100; i++) n += i; n",
+                Duration::from_secs(5),
+            )
+            .unwrap();
+        assert_eq!(4950, rst.to_int().unwrap().value());
+
+        // The budget is rounded up to the granularity of the interrupt checks.
+        let checks = |count| match LimitState::new(ExecutionLimit::Instructions(count)) {
+            LimitState::Checks(n) => n,
+            LimitState::Deadline(_) => unreachable!(),
+        };
+        assert_eq!(0, checks(0));
+        assert_eq!(0, checks(1));
+        assert_eq!(0, checks(10_000));
+        assert_eq!(1, checks(10_001));
+        assert_eq!(9, checks(100_000));
+
+        // The previous limit is restored when the closure panics.
+        let rst = catch_unwind(AssertUnwindSafe(|| {
+            rt.with_execution_limit(ExecutionLimit::Instructions(0), || panic!("boom"))
+        }));
+        assert!(rst.is_err());
+        let rst = ctx
+            .eval(
+                "let m = 0; for (let i = 0; i < 100000; i++) m++; m",
+                "<test>",
+                JS_EVAL_TYPE_GLOBAL as i32,
+            )
+            .unwrap();
+        assert_eq!(100000, rst.to_int().unwrap().value());
+
+        // A caught exception must not be reported as an interruption later on.
+        let rst = ctx.eval(
+            "try { throw 1 } catch (e) { 2 }",
+            "<test>",
+            JS_EVAL_TYPE_GLOBAL as i32,
+        );
+        assert_eq!(2, rst.unwrap().to_int().unwrap().value());
+    }
+
+    #[test]
+    fn test_pending_jobs() {
+        use crate::{JsPromise, PromiseState};
+
+        let rt = Runtime::default();
+        let ctx = &rt.create_context();
+        let flags = JS_EVAL_TYPE_GLOBAL as i32;
+
+        let code = "globalThis.n = 0; Promise.resolve().then(() => n++).then(() => n++); n";
+        assert_eq!(
+            0,
+            ctx.eval(code, "<test>", flags)
+                .unwrap()
+                .to_int()
+                .unwrap()
+                .value()
+        );
+        assert!(rt.is_job_pending());
+        assert_eq!(2, rt.run_jobs_until_idle().unwrap());
+        assert!(!rt.is_job_pending());
+        assert!(!rt.execute_pending_job().unwrap());
+        assert_eq!(
+            2,
+            ctx.eval("n", "<test>", flags)
+                .unwrap()
+                .to_int()
+                .unwrap()
+                .value()
+        );
+
+        let promise: JsPromise = ctx
+            .eval("Promise.resolve(1).then(v => v + 1)", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(PromiseState::Pending, promise.state());
+        assert!(promise.result().is_none());
+        assert_eq!(2, promise.resolve_sync().unwrap().to_int().unwrap().value());
+        assert_eq!(PromiseState::Fulfilled, promise.state());
+
+        let promise: JsPromise = ctx
+            .eval("Promise.reject(new TypeError('bad'))", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(PromiseState::Rejected, promise.state());
+        match promise.resolve_sync() {
+            Err(Error::JsException(ex)) => {
+                assert_eq!(Some("TypeError"), ex.name());
+                assert_eq!("bad", ex.message());
+            }
+            rst => panic!("unexpected result {rst:?}"),
+        }
+        assert!(rt.take_unhandled_rejections().is_empty());
+
+        // Reading the result does not mark the rejection as handled.
+        let promise: JsPromise = ctx
+            .eval("Promise.reject(1)", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(promise.result().unwrap().is_err());
+        assert!(promise.result().unwrap().is_err());
+        assert_eq!(1, rt.take_unhandled_rejections().len());
+        let promise: JsPromise = ctx
+            .eval("Promise.reject(2)", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        promise.mark_handled();
+        assert!(rt.take_unhandled_rejections().is_empty());
+
+        let promise: JsPromise = ctx
+            .eval("new Promise(() => {})", "<test>", flags)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(matches!(
+            promise.resolve_sync(),
+            Err(Error::ExecuteError(_))
+        ));
+
+        let not_promise = ctx.eval("({ then() {} })", "<test>", flags).unwrap();
+        assert!(JsPromise::try_from(not_promise).is_err());
+    }
+
+    #[test]
+    fn test_unhandled_rejection() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let rt = Runtime::default();
+        let ctx = &rt.create_context();
+        let flags = JS_EVAL_TYPE_GLOBAL as i32;
+
+        ctx.eval("Promise.reject(new RangeError('lost'))", "<test>", flags)
+            .unwrap();
+        match rt.run_jobs_until_idle() {
+            Err(Error::JsException(ex)) => {
+                assert_eq!(Some("RangeError"), ex.name());
+                assert_eq!("lost", ex.message());
+            }
+            rst => panic!("unexpected result {rst:?}"),
+        }
+        assert!(rt.run_jobs_until_idle().is_ok());
+
+        // 之后才添加处理函数的拒绝不会被报告
+        ctx.eval(
+            "globalThis.p = Promise.reject(1); Promise.resolve().then(() => p.catch(() => {}))",
+            "<test>",
+            flags,
+        )
+        .unwrap();
+        assert!(rt.run_jobs_until_idle().is_ok());
+        assert!(rt.take_unhandled_rejections().is_empty());
+
+        ctx.eval("Promise.reject(2); Promise.reject(3)", "<test>", flags)
+            .unwrap();
+        assert_eq!(2, rt.take_unhandled_rejections().len());
+
+        // 多个拒绝按顺序一次报告
+        ctx.eval("Promise.reject(6); Promise.reject(7)", "<test>", flags)
+            .unwrap();
+        match rt.run_jobs_until_idle() {
+            Err(Error::UnhandledRejections(errors)) => {
+                let reasons = errors
+                    .iter()
+                    .map(|err| match err {
+                        Error::JsException(ex) => ex.message().to_owned(),
+                        err => panic!("unexpected error {err:?}"),
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(vec!["6", "7"], reasons);
+            }
+            rst => panic!("unexpected result {rst:?}"),
+        }
+        assert!(rt.run_jobs_until_idle().is_ok());
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = events.clone();
+        rt.on_unhandled_rejection(move |_ctx, promise, reason, is_handled| {
+            assert_eq!(crate::PromiseState::Rejected, promise.state());
+            log.borrow_mut()
+                .push((reason.to_int().unwrap().value(), is_handled));
+        });
+        ctx.eval(
+            "globalThis.q = Promise.reject(4); Promise.resolve().then(() => q.catch(() => {}))",
+            "<test>",
+            flags,
+        )
+        .unwrap();
+        assert!(rt.run_jobs_until_idle().is_ok());
+        assert_eq!(vec![(4, false), (4, true)], *events.borrow());
+
+        rt.remove_unhandled_rejection_handler();
+        ctx.eval("Promise.reject(5)", "<test>", flags).unwrap();
+        assert!(rt.run_jobs_until_idle().is_err());
     }
 }
@@ -0,0 +1,345 @@
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+};
+
+#[cfg(feature = "bigint")]
+use crate::JsBigInt;
+use crate::{
+    common::Error,
+    ffi::{JS_GPN_ENUM_ONLY, JS_GPN_STRING_MASK},
+    function::{get_property, own_property_names},
+    FromJs, IntoPropertyKey, JsArray, JsValue,
+};
+
+use super::{at, uint8_array_bytes, PathSegment};
+
+/// Convert `value` to `T`, objects are read from their own enumerable string keyed
+/// properties, `null` and `undefined` are `None` and a `Uint8Array` is read as bytes. Enums
+/// are read from a string for unit variants or from an object with a single key.
+pub fn from_js<T: DeserializeOwned>(value: JsValue) -> Result<T, Error> {
+    T::deserialize(Deserializer { value })
+}
+
+struct Deserializer<'a> {
+    value: JsValue<'a>,
+}
+
+fn object_keys(value: &JsValue) -> Vec<String> {
+    own_property_names(value.ctx, value, JS_GPN_STRING_MASK | JS_GPN_ENUM_ONLY)
+}
+
+fn object_value<'a>(obj: &JsValue<'a>, key: &str) -> Result<JsValue<'a>, Error> {
+    let key = key.into_key(obj.ctx)?;
+    get_property(obj.ctx, obj, &key)
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let Deserializer { value } = self;
+        let ctx = value.ctx;
+
+        #[cfg(feature = "bigint")]
+        if value.is_big_int() {
+            let val = JsBigInt::try_from(value)?;
+            return match val.to_i64() {
+                Ok(val) => visitor.visit_i64(val),
+                Err(_) => visitor.visit_u64(val.to_u64()?),
+            };
+        }
+
+        if value.is_null() || value.is_undefined() {
+            visitor.visit_unit()
+        } else if value.is_bool() {
+            visitor.visit_bool(bool::from_js(ctx, value)?)
+        } else if value.is_number() {
+            // 整数值的 Float64 按整数访问，以便反序列化为整数类型，范围检查与 FromJs 相同
+            if let Ok(val) = u64::from_js(ctx, value.clone()) {
+                visitor.visit_u64(val)
+            } else if let Ok(val) = i64::from_js(ctx, value.clone()) {
+                visitor.visit_i64(val)
+            } else {
+                visitor.visit_f64(f64::from_js(ctx, value)?)
+            }
+        } else if value.is_string() {
+            visitor.visit_string(String::from_js(ctx, value)?)
+        } else if value.is_array() {
+            let array = JsArray::try_from(value)?;
+            let len = array.len()?;
+            visitor.visit_seq(SeqAccess { array, idx: 0, len })
+        } else if let Some(bytes) = uint8_array_bytes(&value)? {
+            visitor.visit_byte_buf(bytes)
+        } else if value.is_object() && !value.is_function() {
+            let keys = object_keys(&value).into_iter();
+            visitor.visit_map(MapAccess {
+                obj: value,
+                keys,
+                key: None,
+            })
+        } else {
+            Err(Error::BadType(format!(
+                "{:?} value can not be deserialized",
+                value.tag()
+            )))
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.value.is_null() || self.value.is_undefined() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match uint8_array_bytes(&self.value)? {
+            Some(bytes) => visitor.visit_byte_buf(bytes),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let Deserializer { value } = self;
+        let ctx = value.ctx;
+
+        if value.is_string() {
+            let variant = String::from_js(ctx, value)?;
+            return visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(variant));
+        }
+
+        let keys = if value.is_object() {
+            object_keys(&value)
+        } else {
+            Vec::new()
+        };
+        let [variant] = <[String; 1]>::try_from(keys).map_err(|_| {
+            Error::BadType(format!(
+                "Enum need a string or an object with a single key but get {:?}",
+                value.tag()
+            ))
+        })?;
+
+        let value = object_value(&value, &variant)?;
+        visitor.visit_enum(EnumAccess { variant, value })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SeqAccess<'a> {
+    array: JsArray<'a>,
+    idx: u32,
+    len: u32,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.idx >= self.len {
+            return Ok(None);
+        }
+
+        let idx = self.idx;
+        self.idx += 1;
+        let value = self.array.get(idx)?;
+        seed.deserialize(Deserializer { value })
+            .map(Some)
+            .map_err(|err| at(err, PathSegment::Index(idx as usize)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.len - self.idx) as usize)
+    }
+}
+
+struct MapAccess<'a> {
+    obj: JsValue<'a>,
+    keys: std::vec::IntoIter<String>,
+    key: Option<String>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some(key) = self.keys.next() else {
+            return Ok(None);
+        };
+
+        let rst = seed.deserialize(MapKey { key: &key });
+        self.key = Some(key);
+
+        rst.map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::GeneralError("next_value() before next_key()".into()))?;
+
+        object_value(&self.obj, &key)
+            .and_then(|value| seed.deserialize(Deserializer { value }))
+            .map_err(|err| at(err, PathSegment::Key(&key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.keys.len())
+    }
+}
+
+/// Deserializes an object key, numeric keys are parsed back from their string form so maps
+/// like `HashMap<u32, _>` round-trip.
+struct MapKey<'a> {
+    key: &'a str,
+}
+
+macro_rules! deserialize_numeric_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.key.parse() {
+                    Ok(key) => visitor.$visit(key),
+                    Err(_) => Err(de::Error::invalid_type(de::Unexpected::Str(self.key), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKey<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_str(self.key)
+    }
+
+    deserialize_numeric_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        IntoDeserializer::<Error>::into_deserializer(self.key)
+            .deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+struct EnumAccess<'a> {
+    variant: String,
+    value: JsValue<'a>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumAccess<'a> {
+    type Error = Error;
+    type Variant = VariantAccess<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess<'a>), Error> {
+        let EnumAccess { variant, value } = self;
+        let val = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(
+            variant.as_str(),
+        ))?;
+
+        Ok((val, VariantAccess { variant, value }))
+    }
+}
+
+struct VariantAccess<'a> {
+    variant: String,
+    value: JsValue<'a>,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        let VariantAccess { variant, value } = self;
+        seed.deserialize(Deserializer { value })
+            .map_err(|err| at(err, PathSegment::Key(&variant)))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        let VariantAccess { variant, value } = self;
+        de::Deserializer::deserialize_seq(Deserializer { value }, visitor)
+            .map_err(|err| at(err, PathSegment::Key(&variant)))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let VariantAccess { variant, value } = self;
+        de::Deserializer::deserialize_map(Deserializer { value }, visitor)
+            .map_err(|err| at(err, PathSegment::Key(&variant)))
+    }
+}
@@ -0,0 +1,202 @@
+mod de;
+mod ser;
+
+use std::fmt::Display;
+
+use crate::{
+    common::{Error, SerdeError},
+    JsTypedArray, JsValue,
+};
+
+pub use de::from_js;
+pub use ser::to_js;
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Serde(SerdeError {
+            path: String::new(),
+            message: msg.to_string(),
+        })
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        <Error as serde::ser::Error>::custom(msg)
+    }
+}
+
+/// A step of the path from the root value to a nested value.
+enum PathSegment<'s> {
+    Key(&'s str),
+    Index(usize),
+}
+
+/// Prefix the path of `err` with `segment`, other errors become a [`SerdeError`] at the
+/// segment.
+fn at(err: Error, segment: PathSegment) -> Error {
+    let mut err = match err {
+        Error::Serde(err) => err,
+        err => SerdeError {
+            path: String::new(),
+            message: err.to_string(),
+        },
+    };
+
+    let mut path = match segment {
+        PathSegment::Key(key) => key.to_owned(),
+        PathSegment::Index(idx) => format!("[{idx}]"),
+    };
+    if !err.path.is_empty() && !err.path.starts_with('[') {
+        path.push('.');
+    }
+    path.push_str(&err.path);
+    err.path = path;
+
+    Error::Serde(err)
+}
+
+/// Copy the bytes of `value` if it is a `Uint8Array`, a detached array has no bytes.
+fn uint8_array_bytes(value: &JsValue) -> Result<Option<Vec<u8>>, Error> {
+    if !value.is_typed_array::<u8>() {
+        return Ok(None);
+    }
+
+    let array = JsTypedArray::<u8>::try_from(value.clone())?;
+    if array.is_detached() {
+        return Ok(Some(Vec::new()));
+    }
+
+    Ok(Some(array.to_vec()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{common::Error, from_js, to_js, Context, Runtime};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Point(i32, i32),
+        Rect { width: u32, height: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        tags: Vec<String>,
+        note: Option<String>,
+        shapes: Vec<Shape>,
+        counts: BTreeMap<String, u64>,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    }
+
+    #[test]
+    fn test_serde() {
+        let rt = Runtime::default();
+        let ctx = &Context::new(&rt);
+        let flags = crate::ffi::JS_EVAL_TYPE_GLOBAL as i32;
+
+        let item = Item {
+            name: "a".to_owned(),
+            tags: vec!["x".to_owned(), "y".to_owned()],
+            note: None,
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Point(1, -2),
+                Shape::Rect {
+                    width: 3,
+                    height: 4,
+                },
+            ],
+            counts: BTreeMap::from([("k".to_owned(), 1 << 40)]),
+            data: vec![1, 2, 255],
+        };
+
+        let val = to_js(ctx, &item).unwrap();
+        ctx.get_global_object()
+            .set_property("item", val.clone())
+            .unwrap();
+        let rst = ctx
+            .eval(
+                "item.data instanceof Uint8Array && item.note === null",
+                "<test>",
+                flags,
+            )
+            .unwrap();
+        assert!(rst.to_bool().unwrap().value());
+        assert_eq!(
+            r#"["Empty",{"Circle":1.5},{"Point":[1,-2]},{"Rect":{"width":3,"height":4}}]"#,
+            val.get_property("shapes")
+                .unwrap()
+                .to_json(None, None)
+                .unwrap()
+        );
+        assert_eq!(item, from_js::<Item>(val).unwrap());
+
+        // 由脚本创建的值，Float64 的整数值也可以反序列化为整数
+        let script = r#"({
+            name: "b", tags: [], note: "n", counts: { k: 6 / 2 },
+            shapes: [{ Rect: { width: 1, height: 2 } }], data: new Uint8Array([7]),
+        })"#;
+        let val = ctx.eval(script, "<test>", flags).unwrap();
+        let item: Item = from_js(val).unwrap();
+        assert_eq!(Some("n"), item.note.as_deref());
+        assert_eq!(3, item.counts["k"]);
+        assert_eq!(vec![7], item.data);
+
+        // 错误带有出错值的路径
+        let script = r#"({
+            name: "c", tags: ["x", 1], note: null, counts: {}, shapes: [], data: new Uint8Array(),
+        })"#;
+        let val = ctx.eval(script, "<test>", flags).unwrap();
+        let Err(Error::Serde(err)) = from_js::<Item>(val) else {
+            panic!("expected a serde error");
+        };
+        assert_eq!("tags[1]", err.path());
+
+        let val = ctx
+            .eval("[{ Rect: { width: -1, height: 2 } }]", "<test>", flags)
+            .unwrap();
+        let Err(Error::Serde(err)) = from_js::<Vec<Shape>>(val) else {
+            panic!("expected a serde error");
+        };
+        assert_eq!("[0].Rect.width", err.path());
+
+        // 数字键以字符串保存，反序列化时再解析回来
+        let map = HashMap::from([(1u32, "a".to_owned()), (20, "b".to_owned())]);
+        let val = to_js(ctx, &map).unwrap();
+        assert_eq!(map, from_js::<HashMap<u32, String>>(val).unwrap());
+        let val = ctx.eval("({ '-1': 1.5, x: 2 })", "<test>", flags).unwrap();
+        assert!(from_js::<HashMap<i32, f64>>(val).is_err());
+
+        // "__proto__" 键成为自身属性，不会改变原型
+        let map = BTreeMap::from([("__proto__".to_owned(), vec![1])]);
+        let val = to_js(ctx, &map).unwrap();
+        ctx.get_global_object()
+            .set_property("proto", val.clone())
+            .unwrap();
+        let rst = ctx
+            .eval(
+                "Object.getPrototypeOf(proto) === Object.prototype && Object.keys(proto).length === 1",
+                "<test>",
+                flags,
+            )
+            .unwrap();
+        assert!(rst.to_bool().unwrap().value());
+        assert_eq!(map, from_js::<BTreeMap<String, Vec<i32>>>(val).unwrap());
+
+        let map = BTreeMap::from([((1, 2), "x")]);
+        let Err(Error::Serde(err)) = to_js(ctx, &vec![map]) else {
+            panic!("expected a serde error");
+        };
+        assert_eq!("[0]", err.path());
+    }
+}
@@ -0,0 +1,393 @@
+use serde::ser::{self, Serialize};
+
+use crate::{
+    common::Error, ffi::JS_PROP_C_W_E, function::js_to_string, Context, IntoJs, JsArray,
+    JsTypedArray, JsValue, JS_NULL,
+};
+
+use super::{at, PathSegment};
+
+/// Convert `value` to a JS value, structs and maps become objects, sequences become arrays,
+/// `None` and `()` become `null` and bytes become a `Uint8Array`. Enums are externally
+/// tagged, like `{ "Variant": value }`.
+pub fn to_js<'a, T>(ctx: &'a Context, value: &T) -> Result<JsValue<'a>, Error>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer { ctx })
+}
+
+struct Serializer<'a> {
+    ctx: &'a Context<'a>,
+}
+
+/// Wrap `value` into `{ variant: value }`.
+fn wrap_variant<'a>(
+    ctx: &'a Context,
+    variant: &'static str,
+    value: JsValue<'a>,
+) -> Result<JsValue<'a>, Error> {
+    let obj = ctx.new_object()?;
+    obj.define_property(variant, value, JS_PROP_C_W_E as i32)?;
+
+    Ok(obj)
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray<'a>;
+    type SerializeTuple = SerializeArray<'a>;
+    type SerializeTupleStruct = SerializeArray<'a>;
+    type SerializeTupleVariant = SerializeArray<'a>;
+    type SerializeMap = SerializeObject<'a>;
+    type SerializeStruct = SerializeObject<'a>;
+    type SerializeStructVariant = SerializeObject<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_char(self, v: char) -> Result<JsValue<'a>, Error> {
+        v.encode_utf8(&mut [0; 4]).into_js(self.ctx)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JsValue<'a>, Error> {
+        v.into_js(self.ctx)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<JsValue<'a>, Error> {
+        JsTypedArray::new(self.ctx, v.to_vec()).map(JsValue::from)
+    }
+
+    fn serialize_none(self) -> Result<JsValue<'a>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<JsValue<'a>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JsValue<'a>, Error> {
+        Ok(JsValue::new(self.ctx, JS_NULL))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JsValue<'a>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<JsValue<'a>, Error> {
+        variant.into_js(self.ctx)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<JsValue<'a>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<JsValue<'a>, Error> {
+        let ctx = self.ctx;
+        let value = value
+            .serialize(self)
+            .map_err(|err| at(err, PathSegment::Key(variant)))?;
+
+        wrap_variant(ctx, variant, value)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeArray<'a>, Error> {
+        Ok(SerializeArray {
+            array: JsArray::new_empty(self.ctx)?,
+            len: 0,
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray<'a>, Error> {
+        let mut seq = self.serialize_seq(Some(len))?;
+        seq.variant = Some(variant);
+
+        Ok(seq)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeObject<'a>, Error> {
+        Ok(SerializeObject {
+            obj: self.ctx.new_object()?,
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeObject<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeObject<'a>, Error> {
+        let mut map = self.serialize_map(Some(len))?;
+        map.variant = Some(variant);
+
+        Ok(map)
+    }
+}
+
+/// Serializes sequences, tuples and tuple variants into an array.
+struct SerializeArray<'a> {
+    array: JsArray<'a>,
+    len: usize,
+    variant: Option<&'static str>,
+}
+
+impl<'a> SerializeArray<'a> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let ctx = self.array.context();
+        let rst = value
+            .serialize(Serializer { ctx })
+            .and_then(|value| self.array.push(value));
+        self.len += 1;
+
+        rst.map_err(|err| match self.variant {
+            Some(variant) => at(
+                at(err, PathSegment::Index(self.len - 1)),
+                PathSegment::Key(variant),
+            ),
+            None => at(err, PathSegment::Index(self.len - 1)),
+        })
+    }
+
+    fn finish(self) -> Result<JsValue<'a>, Error> {
+        let ctx = self.array.context();
+        match self.variant {
+            Some(variant) => wrap_variant(ctx, variant, self.array.into()),
+            None => Ok(self.array.into()),
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for SerializeArray<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SerializeArray<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SerializeArray<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SerializeArray<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
+
+/// Serializes maps, structs and struct variants into an object.
+struct SerializeObject<'a> {
+    obj: JsValue<'a>,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl<'a> SerializeObject<'a> {
+    // 定义而不是赋值，"__proto__" 之类的键不会触发 setter
+    fn set<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        let ctx = self.obj.ctx;
+        let rst = value
+            .serialize(Serializer { ctx })
+            .and_then(|value| self.obj.define_property(key, value, JS_PROP_C_W_E as i32));
+
+        rst.map_err(|err| match self.variant {
+            Some(variant) => at(at(err, PathSegment::Key(key)), PathSegment::Key(variant)),
+            None => at(err, PathSegment::Key(key)),
+        })
+    }
+
+    fn finish(self) -> Result<JsValue<'a>, Error> {
+        match self.variant {
+            Some(variant) => wrap_variant(self.obj.ctx, variant, self.obj),
+            None => Ok(self.obj),
+        }
+    }
+}
+
+impl<'a> ser::SerializeMap for SerializeObject<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        // 对象的键只能是字符串，数字键转换为字符串
+        let ctx = self.obj.ctx;
+        let key = key.serialize(Serializer { ctx })?;
+        if !key.is_string() && !key.is_number() {
+            Err(Error::BadType(format!(
+                "Map key must be a string or a number but get {:?}",
+                key.tag()
+            )))?
+        }
+        self.key = Some(js_to_string(ctx, &key)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| {
+            Error::GeneralError("serialize_value() before serialize_key()".into())
+        })?;
+
+        self.set(&key, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for SerializeObject<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.set(key, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for SerializeObject<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.set(key, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        self.finish()
+    }
+}
@@ -0,0 +1,94 @@
+use ez_quick_js::{
+    common::Error, ffi::JS_EVAL_TYPE_GLOBAL, function::js_to_string, js_methods, JsClass, Runtime,
+};
+
+#[derive(JsClass)]
+struct Counter {
+    #[js(get, set)]
+    step: i32,
+    #[js(get, name = "total")]
+    count: i32,
+}
+
+#[js_methods]
+impl Counter {
+    fn new(step: Option<i32>) -> Self {
+        Self {
+            step: step.unwrap_or(1),
+            count: 0,
+        }
+    }
+
+    fn add(&mut self) -> i32 {
+        self.count += self.step;
+        self.count
+    }
+
+    #[js(name = "addMany")]
+    fn add_many(&mut self, times: u32) -> Result<i32, Error> {
+        if times > 100 {
+            return Err(Error::ValueError("too many times".to_owned()));
+        }
+        for _ in 0..times {
+            self.add();
+        }
+        Ok(self.count)
+    }
+
+    #[js(getter)]
+    fn double(&self) -> i32 {
+        self.count * 2
+    }
+
+    #[js(setter)]
+    fn set_double(&mut self, val: i32) {
+        self.count = val / 2;
+    }
+
+    fn describe(prefix: String) -> String {
+        format!("{prefix} Counter")
+    }
+
+    #[js(skip)]
+    #[allow(dead_code)]
+    fn internal(&self) -> &i32 {
+        &self.count
+    }
+}
+
+#[test]
+fn test_class() {
+    let rt = Runtime::default();
+    let ctx = &rt.create_context();
+    ctx.register_class::<Counter>().unwrap();
+
+    let script = r#"
+        let c = new Counter(2);
+        c.add();
+        c.addMany(3);
+        let rst = [c.total, c.step, c.double, Counter.describe("A")];
+        c.step = 5;
+        c.double = 40;
+        c.add();
+        rst.push(c.total, new Counter().add(), c instanceof Counter, typeof c.internal);
+        try { c.addMany(1000); } catch (e) { rst.push(e.message); }
+        try { Counter.prototype.add.call({}); } catch (e) { rst.push(e.name); }
+        try { c.step = "x"; } catch (e) { rst.push(e.name); }
+        rst.join("|")
+    "#;
+    let rst = ctx
+        .eval(script, "<test>", JS_EVAL_TYPE_GLOBAL as i32)
+        .unwrap();
+    assert_eq!(
+        "8|2|16|A Counter|25|1|true|undefined|Value error: too many times|TypeError|TypeError",
+        js_to_string(ctx, &rst).unwrap()
+    );
+
+    let c = ctx.eval("c", "<test>", JS_EVAL_TYPE_GLOBAL as i32).unwrap();
+    assert_eq!(5, c.borrow_opaque::<Counter>().unwrap().step);
+    c.borrow_opaque_mut::<Counter>().unwrap().count = 1;
+    let total = ctx
+        .eval("c.total", "<test>", JS_EVAL_TYPE_GLOBAL as i32)
+        .unwrap();
+    assert_eq!("1", js_to_string(ctx, &total).unwrap());
+}